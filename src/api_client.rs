@@ -2,7 +2,7 @@ use std::sync::{Arc, Mutex};
 
 use log::{debug, error, info, warn};
 use reqwest_cookie_store::CookieStore;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 
 use crate::{
@@ -11,9 +11,13 @@ use crate::{
         params::*,
         urls::{URL_LOGIN, URL_SEND_CODE},
     },
-    error::{Error, Result},
+    error::{Error, ErrorContext, Result, ResultExt},
     http_client::{HttpClient, HttpResponse},
+    models::WithRaw,
+    secret::{Secret, mask_phone},
     session::Session,
+    users::ScreenNameCache,
+    utils,
 };
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -29,6 +33,9 @@ pub struct ApiClient<C: HttpClient> {
     pub client: C,
     pub config: Config,
     login_state: Arc<Mutex<LoginState>>,
+    pub(crate) screen_names: Arc<Mutex<ScreenNameCache>>,
+    /// Newest home timeline status `poll_new` has returned.
+    pub(crate) home_newest_id: Arc<Mutex<Option<i64>>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -36,7 +43,10 @@ pub enum LoginState {
     #[default]
     Init,
     WaitingForCode {
-        phone_number: String,
+        phone_number: Secret<String>,
+    },
+    WaitingForQrCode {
+        qrid: String,
     },
     LoggedIn {
         session: Session,
@@ -52,6 +62,10 @@ impl LoginState {
         matches!(self, Self::WaitingForCode { .. })
     }
 
+    pub fn is_waiting_for_qrcode(&self) -> bool {
+        matches!(self, Self::WaitingForQrCode { .. })
+    }
+
     pub fn is_logged_in(&self) -> bool {
         matches!(self, Self::LoggedIn { .. })
     }
@@ -64,6 +78,8 @@ impl<C: HttpClient> ApiClient<C> {
             client,
             config,
             login_state: Default::default(),
+            screen_names: Default::default(),
+            home_newest_id: Default::default(),
         }
     }
 
@@ -74,6 +90,18 @@ impl<C: HttpClient> ApiClient<C> {
             .clone()
     }
 
+    /// Logging in forgets what was cached for the previous account.
+    pub(crate) fn set_login_state(&self, login_state: LoginState) {
+        if login_state.is_logged_in() {
+            *self.screen_names.lock().expect("screen names lock failed") = Default::default();
+            *self
+                .home_newest_id
+                .lock()
+                .expect("home newest id lock failed") = None;
+        }
+        *self.login_state.lock().expect("login state lock failed") = login_state;
+    }
+
     #[cfg(any(feature = "test-mocks", test))]
     pub fn from_session(client: C, session: Session) -> Self {
         info!("WeiboClient created from session for user {}", session.uid);
@@ -82,6 +110,8 @@ impl<C: HttpClient> ApiClient<C> {
             client,
             config: Default::default(),
             login_state: Arc::new(Mutex::new(LoginState::LoggedIn { session })),
+            screen_names: Default::default(),
+            home_newest_id: Default::default(),
         }
     }
 
@@ -94,8 +124,63 @@ impl<C: HttpClient> ApiClient<C> {
         }
     }
 
+    /// The session for the mobile api endpoints, which authenticate by gsid.
+    pub(crate) fn app_session(&self) -> Result<Session> {
+        let session = self.session()?;
+        if session.has_gsid() {
+            Ok(session)
+        } else {
+            warn!("app endpoint called with web only session");
+            Err(Error::GsidRequired)
+        }
+    }
+
+    /// The common params of the mobile api, signed with the gsid and `s` of
+    /// the app session.
+    pub(crate) fn signed_params(&self) -> Result<Value> {
+        Ok(Self::signed_params_for(&self.app_session()?))
+    }
+
+    /// Same as `signed_params`, for a session already checked with
+    /// `app_session`.
+    pub(crate) fn signed_params_for(session: &Session) -> Value {
+        let mut params = utils::build_common_params();
+        params["gsid"] = session.gsid.expose().clone().into();
+        params["s"] = utils::generate_s(&session.uid, FROM).into();
+        params
+    }
+
+    /// Sends a GET and parses the body, see `WithRaw::from_api_value`.
+    pub(crate) async fn get_with_raw<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        params: &Value,
+    ) -> Result<WithRaw<T>> {
+        let response = self
+            .client
+            .get(url, params, self.config.retry_times, self.config.timeout)
+            .await?;
+        WithRaw::from_response(response).await
+    }
+
+    /// Sends a POST and parses the body, see `WithRaw::from_api_value`.
+    pub(crate) async fn post_with_raw<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        form: &Value,
+    ) -> Result<WithRaw<T>> {
+        let response = self
+            .client
+            .post(url, form, self.config.retry_times, self.config.timeout)
+            .await?;
+        WithRaw::from_response(response).await
+    }
+
     pub async fn get_sms_code(&self, phone_number: String) -> Result<()> {
-        info!("getting sms code for phone number: {phone_number}");
+        info!(
+            "getting sms code for phone number: {}",
+            mask_phone(&phone_number)
+        );
         if !self.login_state().is_init() {
             warn!("get_sms_code called not in init state");
         }
@@ -118,9 +203,11 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.retry_times,
                 self.config.timeout,
             )
-            .await?;
-        *self.login_state.lock().expect("login state lock failed") =
-            LoginState::WaitingForCode { phone_number };
+            .await
+            .context("get_sms_code")?;
+        *self.login_state.lock().expect("login state lock failed") = LoginState::WaitingForCode {
+            phone_number: phone_number.into(),
+        };
 
         let send_code_response = response.json::<SendCodeResponse>().await?;
         match send_code_response {
@@ -153,10 +240,11 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.retry_times,
                 self.config.timeout,
             )
-            .await?;
+            .await
+            .context("login")?;
             info!("login success, user: {}", session.uid);
             self.client.set_cookie(session.cookie_store.clone())?;
-            *self.login_state.lock().unwrap() = LoginState::LoggedIn { session };
+            self.set_login_state(LoginState::LoggedIn { session });
             Ok(())
         } else {
             error!("login called in invalid state");
@@ -166,6 +254,9 @@ impl<C: HttpClient> ApiClient<C> {
 
     pub async fn login_with_session(&self, session: Session) -> Result<()> {
         info!("logging in with session for user {}", session.uid);
+        if !session.has_gsid() {
+            return self.login_with_web_session(session).await;
+        }
         if self.login_state().is_init() {
             let payload = json!({
                 "c": PARAM_C,
@@ -184,12 +275,13 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.retry_times,
                 self.config.timeout,
             )
-            .await?;
+            .await
+            .context(ErrorContext::new("login_with_session").param("uid", &session.uid))?;
             info!("login with session success, user: {}", new_session.uid);
             self.client.set_cookie(new_session.cookie_store.clone())?;
-            *self.login_state.lock().expect("login state lock failed") = LoginState::LoggedIn {
+            self.set_login_state(LoginState::LoggedIn {
                 session: new_session,
-            };
+            });
             Ok(())
         } else {
             error!("login_with_session called in invalid state");
@@ -224,7 +316,7 @@ impl TryFrom<LoginSucc> for Session {
     type Error = Error;
     fn try_from(value: LoginSucc) -> std::result::Result<Self, Self::Error> {
         Ok(Self {
-            gsid: value.gsid,
+            gsid: value.gsid.into(),
             uid: value.uid,
             user: value.user,
             cookie_store: TryInto::<CookieStore>::try_into(value.cookie)?,
@@ -285,7 +377,7 @@ mod local_tests {
         weibo_api.get_sms_code(phone_number.clone()).await.unwrap();
 
         assert!(
-            matches!(weibo_api.login_state(), LoginState::WaitingForCode { phone_number: num } if num == phone_number)
+            matches!(weibo_api.login_state(), LoginState::WaitingForCode { phone_number: num } if *num.expose() == phone_number)
         );
     }
 
@@ -306,8 +398,10 @@ mod local_tests {
             config: Default::default(),
             client: mock_client.clone(),
             login_state: Arc::new(Mutex::new(LoginState::WaitingForCode {
-                phone_number: phone_number.clone(),
+                phone_number: phone_number.clone().into(),
             })),
+            screen_names: Default::default(),
+            home_newest_id: Default::default(),
         };
 
         weibo_api.login(&sms_code).await.unwrap();
@@ -317,7 +411,7 @@ mod local_tests {
 
         assert!(weibo_api.login_state().is_logged_in());
         if let Ok(session) = weibo_api.session() {
-            assert_eq!(session.gsid, mock_gsid);
+            assert_eq!(session.gsid.expose(), mock_gsid);
             assert_eq!(session.uid, mock_uid);
         } else {
             panic!("Login state should be LoggedIn");
@@ -328,7 +422,7 @@ mod local_tests {
     async fn test_login_with_session() {
         let mock_client = MockClient::new();
         let old_session = Session {
-            gsid: "old_gsid".to_string(),
+            gsid: "old_gsid".into(),
             uid: "test_uid".to_string(),
             user: Value::Null,
             cookie_store: Default::default(),
@@ -346,7 +440,7 @@ mod local_tests {
         assert!(weibo_api.login_state().is_logged_in());
         let new_gsid = login_response_json["gsid"].as_str().unwrap();
         if let Ok(session) = weibo_api.session() {
-            assert_eq!(session.gsid, new_gsid);
+            assert_eq!(session.gsid.expose(), new_gsid);
         } else {
             panic!("Login state should be LoggedIn");
         }
@@ -0,0 +1,169 @@
+use futures::Stream;
+use log::{debug, info};
+use serde_json::Value;
+
+use crate::{
+    api_client::ApiClient,
+    constants::urls::{URL_ATTITUDES_CREATE, URL_ATTITUDES_DESTROY, URL_ATTITUDES_SHOW},
+    error::{ErrorContext, Result, ResultExt},
+    http_client::HttpClient,
+    models::{Attitude, AttitudePage, WithRaw},
+    paging::paged_stream,
+};
+
+/// Users per page, the most the api returns.
+const PAGE_SIZE: u32 = 50;
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Reacts to status `id` with `attitude`, replacing any earlier one.
+    pub async fn attitudes_create(&self, id: i64, attitude: Attitude) -> Result<()> {
+        info!("creating attitude, id: {id}, attitude: {attitude}");
+        let mut params = self.signed_params()?;
+        params["id"] = id.into();
+        params["attitude"] = attitude.as_str().into();
+        self.post_with_raw::<Value>(URL_ATTITUDES_CREATE, &params)
+            .await
+            .context(
+                ErrorContext::new("attitudes_create")
+                    .param("id", id)
+                    .param("attitude", &attitude),
+            )?;
+        debug!("attitude on {id} created");
+        Ok(())
+    }
+
+    /// Takes back the reaction to status `id`.
+    pub async fn attitudes_destroy(&self, id: i64) -> Result<()> {
+        info!("destroying attitude, id: {id}");
+        let mut params = self.signed_params()?;
+        params["id"] = id.into();
+        self.post_with_raw::<Value>(URL_ATTITUDES_DESTROY, &params)
+            .await
+            .context(ErrorContext::new("attitudes_destroy").param("id", id))?;
+        debug!("attitude on {id} destroyed");
+        Ok(())
+    }
+
+    /// Users who reacted to status `id`, newest first, pages start at 1.
+    pub async fn attitudes_show(&self, id: i64, page: u32) -> Result<AttitudePage> {
+        Ok(self.attitudes_show_with_raw(id, page).await?.value)
+    }
+
+    /// Same as `attitudes_show`, along with the JSON as it was received.
+    pub async fn attitudes_show_with_raw(
+        &self,
+        id: i64,
+        page: u32,
+    ) -> Result<WithRaw<AttitudePage>> {
+        info!("getting attitudes, id: {id}, page: {page}");
+        let mut params = self.signed_params()?;
+        params["id"] = id.into();
+        params["page"] = page.into();
+        params["count"] = PAGE_SIZE.into();
+
+        self.get_with_raw(URL_ATTITUDES_SHOW, &params)
+            .await
+            .context(
+                ErrorContext::new("attitudes_show")
+                    .param("id", id)
+                    .param("page", page),
+            )
+    }
+
+    /// Pages of `attitudes_show` until the results run out.
+    pub fn attitudes_show_stream(&self, id: i64) -> impl Stream<Item = Result<AttitudePage>> + '_ {
+        paged_stream(move |page| self.attitudes_show(id, page))
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use crate::{api_client::ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_attitudes_show() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let _ = weibo_api.attitudes_show(5186000000000001, 1).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{
+        error::Error,
+        mock::{MockClient, logged_in_client},
+    };
+
+    fn api_client() -> ApiClient<MockClient> {
+        logged_in_client(MockClient::new())
+    }
+
+    #[tokio::test]
+    async fn test_attitudes_create_and_destroy() {
+        let weibo_api = api_client();
+        weibo_api
+            .client
+            .set_attitudes_create_response_from_str(r#"{"id": 1, "attitude": "haha"}"#);
+        weibo_api
+            .attitudes_create(5186000000000001, Attitude::Haha)
+            .await
+            .unwrap();
+
+        weibo_api.client.set_attitudes_destroy_response_from_str(
+            r#"{"errmsg": "target weibo does not exist!", "errno": 20101, "errtype": "DEFAULT_ERROR", "isblock": false}"#,
+        );
+        let error = weibo_api
+            .attitudes_destroy(5186000000000001)
+            .await
+            .unwrap_err();
+        assert!(matches!(error.kind(), Error::ApiError(e) if e.errno == 20101));
+        assert_eq!(error.context().unwrap().endpoint, "attitudes_destroy");
+    }
+
+    #[tokio::test]
+    async fn test_attitudes_show() {
+        let weibo_api = api_client();
+        weibo_api
+            .client
+            .set_attitudes_show_response_from_file(Path::new("tests/data/attitudes_show.json"))
+            .unwrap();
+        let page = weibo_api.attitudes_show(5186000000000001, 1).await.unwrap();
+        assert_eq!(page.total_number, 3);
+        let attitudes: Vec<_> = page.attitudes.iter().map(|a| a.attitude.clone()).collect();
+        assert_eq!(
+            attitudes,
+            [
+                Attitude::Heart,
+                Attitude::Haha,
+                Attitude::Other("doge".into())
+            ]
+        );
+        assert_eq!(
+            page.attitudes[1].user.as_ref().unwrap().screen_name,
+            "liker_two"
+        );
+
+        let pages: Vec<_> = weibo_api
+            .attitudes_show_stream(5186000000000001)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 1);
+
+        weibo_api.client.set_attitudes_show_response_from_str(
+            r#"{"attitudes": [{"id": 1, "attitude": null}, {"id": 2, "attitude": "wow"}], "total_number": 2}"#,
+        );
+        let page = weibo_api.attitudes_show(5186000000000001, 1).await.unwrap();
+        let attitudes: Vec<_> = page.attitudes.iter().map(|a| a.attitude.clone()).collect();
+        assert_eq!(attitudes, [Attitude::Heart, Attitude::Wow]);
+    }
+}
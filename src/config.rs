@@ -29,6 +29,23 @@ pub struct Config {
     pub retry_times: u8,
     #[serde(with = "duration_as_secs")]
     pub timeout: Duration,
+    /// Typed fetchers replace truncated texts with the full long text.
+    #[serde(default)]
+    pub expand_long_text: bool,
+    /// How many long texts are fetched at once when expanding.
+    #[serde(default = "default_long_text_concurrency")]
+    pub long_text_concurrency: usize,
+    /// How many screen name to uid lookups `resolve_uid` keeps.
+    #[serde(default = "default_screen_name_cache_size")]
+    pub screen_name_cache_size: usize,
+}
+
+fn default_long_text_concurrency() -> usize {
+    4
+}
+
+fn default_screen_name_cache_size() -> usize {
+    256
 }
 
 impl Default for Config {
@@ -36,6 +53,9 @@ impl Default for Config {
         Self {
             retry_times: 2,
             timeout: Duration::from_secs(10),
+            expand_long_text: false,
+            long_text_concurrency: default_long_text_concurrency(),
+            screen_name_cache_size: default_screen_name_cache_size(),
         }
     }
 }
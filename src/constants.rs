@@ -12,9 +12,27 @@ pub mod urls {
     pub const URL_SEND_CODE: &str = full_url!("/2/account/login_sendcode");
     pub const URL_LOGIN: &str = full_url!("/2/account/login");
     pub const URL_FAVORITES_DESTROY: &str = full_url!("/2/favorites/destroy");
+    pub const URL_LIKED_STATUSES: &str = full_url!("/2/like/statuses");
+    pub const URL_ATTITUDES_CREATE: &str = full_url!("/2/attitudes/create");
+    pub const URL_ATTITUDES_DESTROY: &str = full_url!("/2/attitudes/destroy");
+    pub const URL_ATTITUDES_SHOW: &str = full_url!("/2/attitudes/show");
     pub const URL_BUILD_COMMENTS: &str = full_url!("/2/comments/build_comments");
     pub const URL_STATUSES_SHOW: &str = full_url!("/2/statuses/show");
     pub const URL_PROFILE_STATUSES: &str = full_url!("/2/profile/statuses");
+    pub const URL_PROFILE: &str = full_url!("/2/profile");
+    pub const URL_USERS_SHOW: &str = full_url!("/2/users/show");
+    pub const URL_FRIENDS_TIMELINE: &str = full_url!("/2/statuses/friends_timeline");
+    pub const URL_SEARCH_STATUSES: &str = full_url!("/2/search/statuses");
+    pub const URL_SEARCH_USERS: &str = full_url!("/2/search/users");
+    pub const URL_CARDLIST: &str = full_url!("/2/cardlist");
+    pub const URL_FRIENDSHIPS_FRIENDS: &str = full_url!("/2/friendships/friends");
+    pub const URL_FRIENDSHIPS_FOLLOWERS: &str = full_url!("/2/friendships/followers");
+    pub const URL_QRCODE_IMAGE: &str = "https://login.sina.com.cn/sso/qrcode/image";
+    pub const URL_QRCODE_CHECK: &str = "https://login.sina.com.cn/sso/qrcode/check";
+    pub const URL_SSO_LOGIN: &str = "https://login.sina.com.cn/sso/login.php";
+    pub const URL_WEB_PROFILE_INFO: &str = "https://weibo.com/ajax/profile/info";
+    pub const URL_WEB_CONFIG: &str = "https://weibo.com/ajax/config/get_config";
+    pub const URL_WEB_HOT_SEARCH: &str = "https://weibo.com/ajax/side/hotSearch";
 }
 
 // Common Parameters
@@ -28,4 +46,6 @@ pub mod params {
     pub const PARAM_C: &str = "weicoabroad";
     pub const SOURCE: &str = "4215535043";
     pub const MIX_MEDIA_ENABLE: u8 = 1;
+    pub const QRCODE_ENTRY: &str = "weibo";
+    pub const WEB_DOMAIN: &str = "weibo.com";
 }
@@ -3,6 +3,7 @@
 /// Some code snippts copied from https://github.com/rwf2/cookie-rs/blob/master/src/parse.rs
 use std::collections::HashMap;
 
+use cookie_store::{CookieDomain, CookieExpiration};
 use log::{error, warn};
 use reqwest_cookie_store::{CookieStore, RawCookie};
 use serde::Deserialize;
@@ -40,6 +41,160 @@ impl TryFrom<Cookie> for CookieStore {
     }
 }
 
+/// Builds a cookie store from browser cookies, which are sent to every
+/// subdomain of `domain`.
+pub(crate) fn web_cookie_store<K, V>(
+    domain: &str,
+    cookies: impl IntoIterator<Item = (K, V)>,
+) -> Result<CookieStore>
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    let request_url = url::Url::parse(&format!("https://{domain}/"))
+        .map_err(|e| Error::DataConversionError(format!("{domain}: {e}")))?;
+    let mut cookie_store = CookieStore::new();
+    for (name, value) in cookies {
+        let cookie = RawCookie::build((name.into(), value.into()))
+            .domain(domain.to_string())
+            .path("/")
+            .build();
+        let _ = cookie_store.insert_raw(&cookie, &request_url).map_err(|e| {
+            warn!("cookie {} insert failed: {e}", cookie.name());
+        });
+    }
+    Ok(cookie_store)
+}
+
+/// Splits a `Cookie:` request header into name and value pairs.
+pub(crate) fn parse_cookie_header(header: &str) -> Vec<(&str, &str)> {
+    let header = header.trim();
+    let header = header
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("cookie:"))
+        .map_or(header, |_| &header[7..]);
+    header
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+const COOKIES_TXT_HEADER: &str = "# Netscape HTTP Cookie File";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// Serializes the unexpired cookies in Netscape `cookies.txt` format, sorted so
+/// that the output is stable.
+pub(crate) fn to_cookies_txt(cookie_store: &CookieStore) -> String {
+    let mut lines: Vec<_> = cookie_store
+        .iter_unexpired()
+        .filter_map(|cookie| {
+            let (domain, include_subdomains) = match &cookie.domain {
+                CookieDomain::HostOnly(host) => (host.clone(), "FALSE"),
+                CookieDomain::Suffix(suffix) => (format!(".{suffix}"), "TRUE"),
+                CookieDomain::NotPresent | CookieDomain::Empty => return None,
+            };
+            let http_only = if cookie.http_only().unwrap_or(false) {
+                HTTP_ONLY_PREFIX
+            } else {
+                ""
+            };
+            let secure = if cookie.secure().unwrap_or(false) {
+                "TRUE"
+            } else {
+                "FALSE"
+            };
+            let expires = match cookie.expires {
+                CookieExpiration::AtUtc(expires) => expires.unix_timestamp(),
+                CookieExpiration::SessionEnd => 0,
+            };
+            Some(format!(
+                "{http_only}{domain}\t{include_subdomains}\t{}\t{secure}\t{expires}\t{}\t{}",
+                String::from(&cookie.path),
+                cookie.name(),
+                cookie.value()
+            ))
+        })
+        .collect();
+    lines.sort();
+
+    let mut content = format!("{COOKIES_TXT_HEADER}\n\n");
+    for line in lines {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// Inserts the cookies of a Netscape `cookies.txt` file into `cookie_store`.
+/// Malformed lines are skipped with a warning, like malformed login cookies.
+pub(crate) fn read_cookies_txt(cookie_store: &mut CookieStore, content: &str) -> Result<()> {
+    for line in content.lines().map(str::trim) {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None if line.is_empty() || line.starts_with('#') => continue,
+            None => (line, false),
+        };
+        let mut fields: Vec<_> = line.split('\t').collect();
+        if fields.len() != 7 {
+            fields = line.split_whitespace().collect();
+        }
+        let [
+            domain,
+            include_subdomains,
+            path,
+            secure,
+            expires,
+            name,
+            value,
+        ] = fields[..]
+        else {
+            warn!("malformed cookies.txt line skipped: {line}");
+            continue;
+        };
+        let host = domain.strip_prefix('.').unwrap_or(domain);
+        let request_url = url::Url::parse(&format!("https://{host}{path}"))
+            .map_err(|e| Error::DataConversionError(format!("{domain}{path}: {e}")))?;
+
+        let mut cookie = RawCookie::build((name.to_string(), value.to_string()))
+            .path(path.to_string())
+            .secure(secure.eq_ignore_ascii_case("TRUE"))
+            .http_only(http_only);
+        if include_subdomains.eq_ignore_ascii_case("TRUE") {
+            cookie = cookie.domain(host.to_string());
+        }
+        match parse_expires(expires) {
+            Some(Some(expires)) => cookie = cookie.expires(expires),
+            Some(None) => {}
+            None => {
+                warn!("cookie {name} has malformed expires {expires}");
+                continue;
+            }
+        }
+
+        let _ = cookie_store
+            .insert_raw(&cookie.build(), &request_url)
+            .map_err(|e| {
+                warn!("cookie {name} of {domain} insert failed: {e}");
+            });
+    }
+
+    Ok(())
+}
+
+/// `Some(None)` stands for a session cookie. Besides unix timestamps (which
+/// some exporters write as floats), cookie style dates are accepted.
+fn parse_expires(expires: &str) -> Option<Option<OffsetDateTime>> {
+    match expires.parse::<f64>() {
+        Ok(secs) if secs <= 0.0 => Some(None),
+        Ok(secs) => OffsetDateTime::from_unix_timestamp(secs as i64)
+            .ok()
+            .map(Some),
+        Err(_) => parse_date(expires).map(Some),
+    }
+}
+
 fn parse_cookie<'a>(cookie_str: &'a str) -> Result<RawCookie<'a>> {
     let mut cookie = RawCookie::parse(cookie_str).map_err(|e| {
         error!("{cookie_str} parse failed: {e}");
@@ -83,6 +238,7 @@ fn parse_date(ts: &str) -> Option<OffsetDateTime> {
 mod local_tests {
     use std::path::Path;
 
+    use cookie_store::CookieDomain;
     use reqwest_cookie_store::CookieStore;
     use serde_json::Value;
 
@@ -105,6 +261,71 @@ mod local_tests {
         }
     }
 
+    const COOKIES_TXT: &str = "# Netscape HTTP Cookie File
+# comment line
+
+#HttpOnly_.weibo.com\tTRUE\t/\tTRUE\t4102444800\tSUB\tsub_value
+.weibo.com\tTRUE\t/\tTRUE\t4102444800.5\tSUBP\tsubp_value
+m.weibo.cn\tFALSE\t/api\tFALSE\t0\tMLOGIN\t1
+.weibo.cn\tTRUE\t/\tTRUE\tFri, 01-Jan-2100 00:00:00 GMT\tSCF\tscf_value
+malformed line
+";
+
+    #[test]
+    fn test_parse_cookie_header() {
+        let pairs = super::parse_cookie_header("Cookie: SUB=sub; SUBP=a=b;; XSRF-TOKEN=x ");
+        assert_eq!(
+            pairs,
+            vec![("SUB", "sub"), ("SUBP", "a=b"), ("XSRF-TOKEN", "x")]
+        );
+    }
+
+    #[test]
+    fn test_web_cookie_store() {
+        let cookie_store = super::web_cookie_store("weibo.com", [("SUB", "sub")]).unwrap();
+        let sub = cookie_store.get("weibo.com", "/", "SUB").unwrap();
+        assert!(matches!(sub.domain, CookieDomain::Suffix(ref d) if d == "weibo.com"));
+        let url = url::Url::parse("https://passport.weibo.com/").unwrap();
+        assert_eq!(cookie_store.get_request_values(&url).count(), 1);
+    }
+
+    #[test]
+    fn test_read_cookies_txt() {
+        let mut cookie_store = CookieStore::new();
+        super::read_cookies_txt(&mut cookie_store, COOKIES_TXT).unwrap();
+        assert_eq!(cookie_store.iter_unexpired().count(), 4);
+
+        let sub = cookie_store.get("weibo.com", "/", "SUB").unwrap();
+        assert_eq!(sub.value(), "sub_value");
+        assert_eq!(sub.http_only(), Some(true));
+        assert_eq!(sub.secure(), Some(true));
+        assert!(matches!(sub.domain, CookieDomain::Suffix(ref d) if d == "weibo.com"));
+        assert_eq!(sub.expires_datetime().unwrap().unix_timestamp(), 4102444800);
+
+        let mlogin = cookie_store.get("m.weibo.cn", "/api", "MLOGIN").unwrap();
+        assert!(matches!(mlogin.domain, CookieDomain::HostOnly(ref d) if d == "m.weibo.cn"));
+        assert!(!mlogin.is_persistent());
+
+        let scf = cookie_store.get("weibo.cn", "/", "SCF").unwrap();
+        assert_eq!(scf.expires_datetime().unwrap().unix_timestamp(), 4102444800);
+    }
+
+    #[test]
+    fn test_cookies_txt_round_trip() {
+        let mut cookie_store = CookieStore::new();
+        super::read_cookies_txt(&mut cookie_store, COOKIES_TXT).unwrap();
+        let content = super::to_cookies_txt(&cookie_store);
+        assert!(content.starts_with("# Netscape HTTP Cookie File"));
+        assert!(
+            content.contains("#HttpOnly_.weibo.com\tTRUE\t/\tTRUE\t4102444800\tSUB\tsub_value\n")
+        );
+        assert!(content.contains("m.weibo.cn\tFALSE\t/api\tFALSE\t0\tMLOGIN\t1\n"));
+
+        let mut reloaded = CookieStore::new();
+        super::read_cookies_txt(&mut reloaded, &content).unwrap();
+        assert_eq!(super::to_cookies_txt(&reloaded), content);
+    }
+
     #[test]
     fn test_cookie_to_cookie_store() {
         let s = make_login_response();
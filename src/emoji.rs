@@ -1,14 +1,150 @@
-use log::debug;
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::api_client::ApiClient;
 use crate::constants::{
     params::{LANG, UA},
     urls::{URL_EMOJI_UPDATE, URL_WEB_EMOTICON},
 };
-use crate::error::Result;
-use crate::http_client::HttpClient;
+use crate::error::{Result, ResultExt};
+use crate::http_client::{HttpClient, HttpResponse};
 use crate::utils;
 
+const PHRASE_KEYS: &[&str] = &["phrase", "value", "key"];
+const URL_KEYS: &[&str] = &["url", "icon", "image"];
+const PACKAGE_KEYS: &[&str] = &["title", "package_name", "name"];
+/// Keys of arrays which are plumbing of the payload rather than package names.
+const GENERIC_KEYS: &[&str] = &["data", "list", "card", "cards", "emoticons", "items"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmojiSource {
+    Web,
+    Mobile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Emoji {
+    /// The phrase as it appears in status text, brackets included, e.g. `[doge]`.
+    pub phrase: String,
+    pub url: String,
+    pub package: Option<String>,
+    pub category: Option<String>,
+    pub source: EmojiSource,
+}
+
+/// Emoticon phrases of both the web and the mobile api, keyed by phrase.
+/// Where both know a phrase the web entry wins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmojiCatalog {
+    /// Unix timestamp in milliseconds of the fetch.
+    pub fetched_at: u64,
+    pub emojis: BTreeMap<String, Emoji>,
+}
+
+impl EmojiCatalog {
+    /// Collects the emoticons of a raw web or mobile response. The payloads
+    /// are not documented, so entries are taken from wherever a `[phrase]` and
+    /// an image url sit in one object.
+    pub fn merge_response(&mut self, response: &Value, source: EmojiSource) {
+        self.collect(response, None, source);
+    }
+
+    pub fn get(&self, phrase: &str) -> Option<&Emoji> {
+        self.emojis.get(phrase)
+    }
+
+    pub fn url(&self, phrase: &str) -> Option<&str> {
+        self.get(phrase).map(|emoji| emoji.url.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.emojis.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emojis.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Emoji> {
+        self.emojis.values()
+    }
+
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        let age = (utils::get_current_timestamp_millis() as u64).saturating_sub(self.fetched_at);
+        age >= max_age.as_millis() as u64
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        info!("Loading emoji catalog from {:?}", path.as_ref());
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        info!("Saving emoji catalog to {:?}", path.as_ref());
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn collect(&mut self, value: &Value, package: Option<&str>, source: EmojiSource) {
+        match value {
+            Value::Object(map) => {
+                if let Some(emoji) = emoji_from_object(map, package, source) {
+                    self.emojis.entry(emoji.phrase.clone()).or_insert(emoji);
+                    return;
+                }
+                let own_package = first_str(map, PACKAGE_KEYS);
+                for (key, child) in map {
+                    let child_package = own_package
+                        .or_else(|| {
+                            (child.is_array() && !GENERIC_KEYS.contains(&key.as_str()))
+                                .then_some(key.as_str())
+                        })
+                        .or(package);
+                    self.collect(child, child_package, source);
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.collect(value, package, source);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn first_str<'a>(map: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| map.get(*key).and_then(Value::as_str))
+        .filter(|s| !s.is_empty())
+}
+
+fn emoji_from_object(
+    map: &Map<String, Value>,
+    package: Option<&str>,
+    source: EmojiSource,
+) -> Option<Emoji> {
+    let phrase = PHRASE_KEYS
+        .iter()
+        .filter_map(|key| map.get(*key).and_then(Value::as_str))
+        .find(|phrase| phrase.len() > 2 && phrase.starts_with('[') && phrase.ends_with(']'))?;
+    let url = URL_KEYS
+        .iter()
+        .filter_map(|key| map.get(*key).and_then(Value::as_str))
+        .find(|url| url.starts_with("http"))?;
+    Some(Emoji {
+        phrase: phrase.to_string(),
+        url: url.to_string(),
+        package: package.map(str::to_string),
+        category: first_str(map, &["category"]).map(str::to_string),
+        source,
+    })
+}
+
 impl<C: HttpClient> ApiClient<C> {
     pub async fn fetch_from_web_api(&self) -> Result<C::Response> {
         let url = URL_WEB_EMOTICON;
@@ -21,6 +157,7 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.timeout,
             )
             .await
+            .context("fetch_from_web_api")
     }
 
     pub async fn fetch_from_mobile_api(&self) -> Result<C::Response> {
@@ -42,6 +179,145 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.timeout,
             )
             .await
+            .context("fetch_from_mobile_api")
+    }
+
+    /// Fetches both emoticon apis and merges them. A failing source is
+    /// skipped as long as the other one succeeds.
+    pub async fn emoji_catalog(&self) -> Result<EmojiCatalog> {
+        let mut catalog = EmojiCatalog {
+            fetched_at: utils::get_current_timestamp_millis() as u64,
+            ..Default::default()
+        };
+        let web = match self.fetch_from_web_api().await {
+            Ok(response) => response.json::<Value>().await,
+            Err(e) => Err(e),
+        };
+        let mobile = match self.fetch_from_mobile_api().await {
+            Ok(response) => response.json::<Value>().await,
+            Err(e) => Err(e),
+        };
+        let mut errors = Vec::new();
+        for (response, source) in [(web, EmojiSource::Web), (mobile, EmojiSource::Mobile)] {
+            match response {
+                Ok(response) => catalog.merge_response(&response, source),
+                Err(e) => {
+                    warn!("fetching {source:?} emoticons failed: {e}");
+                    errors.push(e);
+                }
+            }
+        }
+        if errors.len() == 2 {
+            return Err(errors.remove(0));
+        }
+        debug!("emoji catalog fetched, {} emoticons", catalog.len());
+        Ok(catalog)
+    }
+
+    /// Returns the catalog cached at `path`, refreshing it once it is older
+    /// than `max_age`. A stale cache is still returned when refreshing fails,
+    /// so emoticons keep resolving offline.
+    pub async fn emoji_catalog_cached<P: AsRef<Path>>(
+        &self,
+        path: P,
+        max_age: Duration,
+    ) -> Result<EmojiCatalog> {
+        let path = path.as_ref();
+        let cached = path
+            .exists()
+            .then(|| EmojiCatalog::load(path))
+            .transpose()
+            .unwrap_or_else(|e| {
+                warn!("emoji cache {path:?} unreadable: {e}");
+                None
+            });
+        if let Some(cached) = cached.as_ref().filter(|c| !c.is_stale(max_age)) {
+            debug!("emoji cache {path:?} is fresh");
+            return Ok(cached.clone());
+        }
+
+        match self.emoji_catalog().await {
+            Ok(catalog) => {
+                catalog.save(path)?;
+                Ok(catalog)
+            }
+            Err(e) => match cached {
+                Some(cached) => {
+                    warn!("refreshing emoji catalog failed, using stale cache: {e}");
+                    Ok(cached)
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::mock::MockClient;
+
+    fn data_path(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name)
+    }
+
+    fn mock_client() -> MockClient {
+        let client = MockClient::new();
+        client
+            .set_web_emoticon_response_from_file(&data_path("web_emoticon.json"))
+            .unwrap();
+        client
+            .set_emoji_update_response_from_file(&data_path("mobile_emoji.json"))
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_emoji_catalog() {
+        let weibo_api = ApiClient::new(mock_client(), Default::default());
+        let catalog = weibo_api.emoji_catalog().await.unwrap();
+
+        assert_eq!(catalog.len(), 5);
+        let doge = catalog.get("[doge]").unwrap();
+        assert_eq!(doge.source, EmojiSource::Web);
+        assert_eq!(doge.package.as_deref(), Some("默认"));
+        assert!(doge.url.ends_with("2018new_doge02_org.png"));
+        let flower = catalog.get("[浪小花撒花]").unwrap();
+        assert_eq!(flower.category.as_deref(), Some("浪小花"));
+        let minion = catalog.get("[小黄人微笑]").unwrap();
+        assert_eq!(minion.source, EmojiSource::Mobile);
+        assert_eq!(minion.package.as_deref(), Some("小黄人"));
+        assert!(catalog.url("[not an emoji]").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_emoji_catalog_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("emoji.json");
+        let weibo_api = ApiClient::new(mock_client(), Default::default());
+        let fetched = weibo_api
+            .emoji_catalog_cached(&cache, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert!(cache.exists());
+
+        // A fresh cache is served without the network.
+        let offline_api = ApiClient::new(MockClient::new(), Default::default());
+        let cached = offline_api
+            .emoji_catalog_cached(&cache, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(cached, fetched);
+
+        // So is a stale one when refreshing fails.
+        let stale = offline_api
+            .emoji_catalog_cached(&cache, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(stale, fetched);
+        assert!(stale.is_stale(Duration::ZERO));
     }
 }
 
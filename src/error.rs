@@ -1,18 +1,40 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use reqwest::{StatusCode, header::HeaderMap};
 use thiserror::Error;
 
 use crate::api_client::ErrResponse;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    /// The url is stripped as it carries `gsid` and `s`, see `Error::context`.
     #[error("Network request failed: {0}")]
-    NetworkError(#[from] reqwest::Error),
+    NetworkError(reqwest::Error),
 
     #[error("API returned an error: {0:?}")]
     ApiError(ErrResponse),
 
+    /// A non-2xx response. `api_error` is set when the body is a weibo error object.
+    /// `headers` leave out cookies.
+    #[error("HTTP {status} from {url}: {body_snippet}")]
+    HttpStatus {
+        status: StatusCode,
+        url: String,
+        headers: Box<HeaderMap>,
+        body_snippet: String,
+        api_error: Option<ErrResponse>,
+    },
+
     #[error("Failed to deserialize response: {0}")]
     DeserializationError(#[from] serde_json::Error),
 
+    /// A value of the wrong shape, `path` is where it is, e.g. `cards[3].mblog.id`.
+    #[error("Failed to deserialize `{path}`: {source}")]
+    InvalidField {
+        path: String,
+        source: serde_json::Error,
+    },
+
     #[error("Failed to convert data: {0}")]
     DataConversionError(String),
 
@@ -21,6 +43,204 @@ pub enum Error {
 
     #[error("Unlogged in")]
     NotLoggedIn,
+
+    #[error("Download incomplete: expected {expected} bytes, received {received}")]
+    IncompleteDownload { expected: u64, received: u64 },
+
+    #[error("Endpoint requires an app session with gsid, but only web cookies are available")]
+    GsidRequired,
+
+    /// The web cookies logged in with are logged out or expired.
+    #[error("Web cookies are not logged in")]
+    SessionExpired,
+
+    /// Where an error happened, use `kind` to match on the underlying error.
+    #[error("{context}: {source}")]
+    Context {
+        context: Box<ErrorContext>,
+        source: Box<Error>,
+    },
+}
+
+/// The request an error belongs to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorContext {
+    /// `ApiClient` method, e.g. `favorites`.
+    pub endpoint: String,
+    /// Redacted url.
+    pub url: String,
+    /// Params telling pages and items apart, e.g. `page`, `id` or `uid`.
+    pub params: BTreeMap<String, String>,
+    /// Requests sent, retries included, when known.
+    pub attempts: Option<u8>,
+    pub elapsed: Duration,
+}
+
+impl ErrorContext {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl From<&str> for ErrorContext {
+    fn from(endpoint: &str) -> Self {
+        Self::new(endpoint)
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = [&self.endpoint, &self.url]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect();
+        if !self.params.is_empty() {
+            let params: Vec<_> = self
+                .params
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            parts.push(format!("[{}]", params.join(", ")));
+        }
+        if let Some(attempts) = self.attempts {
+            parts.push(format!("after {attempts} attempt(s)"));
+        }
+        if !self.elapsed.is_zero() {
+            parts.push(format!("in {:.2?}", self.elapsed));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl Error {
+    /// The underlying error, without context.
+    pub fn kind(&self) -> &Error {
+        match self {
+            Self::Context { source, .. } => source.kind(),
+            e => e,
+        }
+    }
+
+    pub fn into_kind(self) -> Error {
+        match self {
+            Self::Context { source, .. } => source.into_kind(),
+            e => e,
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Attaches `context`, merged with the one already attached so errors
+    /// are never wrapped twice.
+    pub fn with_context(self, mut context: ErrorContext) -> Error {
+        match self {
+            Self::Context {
+                context: inner,
+                source,
+            } => {
+                if context.endpoint.is_empty() {
+                    context.endpoint = inner.endpoint;
+                }
+                if context.url.is_empty() {
+                    context.url = inner.url;
+                }
+                for (key, value) in inner.params {
+                    context.params.entry(key).or_insert(value);
+                }
+                context.attempts = context.attempts.or(inner.attempts);
+                context.elapsed = context.elapsed.max(inner.elapsed);
+                Self::Context {
+                    context: Box::new(context),
+                    source,
+                }
+            }
+            e => Self::Context {
+                context: Box::new(context),
+                source: Box::new(e),
+            },
+        }
+    }
+
+    /// The HTTP status of failed responses, e.g. to tell 418 blocks from 5xx errors.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self.kind() {
+            Self::NetworkError(e) => e.status(),
+            Self::HttpStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::NetworkError(e.without_url())
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        Self::InvalidField {
+            path: e.path().to_string(),
+            source: e.into_inner(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Names the `ApiClient` method an error comes from. The url, attempts and
+/// time taken are attached where requests are sent.
+pub(crate) trait ResultExt<T> {
+    fn context(self, context: impl Into<ErrorContext>) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn context(self, context: impl Into<ErrorContext>) -> Result<T> {
+        self.map_err(|e| e.with_context(context.into()))
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+
+    #[test]
+    fn test_error_context() {
+        let transport = ErrorContext {
+            url: "https://api.weibo.cn/2/favorites".to_string(),
+            attempts: Some(3),
+            elapsed: Duration::from_millis(1500),
+            ..Default::default()
+        };
+        let error = Error::NotLoggedIn.with_context(transport);
+        let error = error.with_context(ErrorContext {
+            endpoint: "favorites".to_string(),
+            params: BTreeMap::from([("page".to_string(), "7".to_string())]),
+            elapsed: Duration::from_millis(1600),
+            ..Default::default()
+        });
+
+        assert!(matches!(error.kind(), Error::NotLoggedIn));
+        let context = error.context().unwrap();
+        assert_eq!(context.attempts, Some(3));
+        assert_eq!(
+            error.to_string(),
+            "favorites https://api.weibo.cn/2/favorites [page=7] after 3 attempt(s) in 1.60s: Unlogged in"
+        );
+        assert!(matches!(error.into_kind(), Error::NotLoggedIn));
+    }
+}
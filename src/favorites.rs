@@ -1,24 +1,48 @@
+use futures::Stream;
 use log::{debug, info};
+use serde::Deserialize;
 
 use crate::{
     api_client::ApiClient,
     constants::{
         params::*,
-        urls::{URL_FAVORITES, URL_FAVORITES_DESTROY},
+        urls::{URL_FAVORITES, URL_FAVORITES_DESTROY, URL_LIKED_STATUSES},
     },
-    error::Result,
+    error::{ErrorContext, Result, ResultExt},
     http_client::HttpClient,
-    utils,
+    models::{Favorite, Favorites, Status, WithRaw, lenient_number},
+    paging::paged_stream,
 };
 
+/// A page of liked statuses as the api sends it, without the time of the like.
+#[derive(Debug, Default, Deserialize)]
+struct LikedStatuses {
+    #[serde(default)]
+    statuses: Vec<Status>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    total_number: u64,
+}
+
+impl From<LikedStatuses> for Favorites {
+    fn from(liked: LikedStatuses) -> Self {
+        Self {
+            favorites: liked
+                .statuses
+                .into_iter()
+                .map(|status| Favorite {
+                    status,
+                    favorited_time: None,
+                })
+                .collect(),
+            total_number: liked.total_number,
+        }
+    }
+}
+
 impl<C: HttpClient> ApiClient<C> {
     pub async fn favorites(&self, page: u32, count: u32) -> Result<C::Response> {
         info!("getting favorites, page: {page}");
-        let session = self.session()?;
-        let s = utils::generate_s(&session.uid, FROM);
-        let mut params = utils::build_common_params();
-        params["gsid"] = session.gsid.clone().into();
-        params["s"] = s.into();
+        let mut params = self.signed_params()?;
         params["page"] = page.into();
         params["count"] = count.into();
         params["mix_media_enable"] = MIX_MEDIA_ENABLE.into();
@@ -31,15 +55,85 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.timeout,
             )
             .await
+            .context(
+                ErrorContext::new("favorites")
+                    .param("page", page)
+                    .param("count", count),
+            )
+    }
+
+    /// Same as `favorites`, parsed into the typed model. Truncated texts are
+    /// expanded when `config.expand_long_text` is set.
+    pub async fn favorites_typed(&self, page: u32, count: u32) -> Result<Favorites> {
+        Ok(self.favorites_with_raw(page, count).await?.value)
+    }
+
+    /// Same as `favorites_typed`, along with the JSON as it was received.
+    pub async fn favorites_with_raw(&self, page: u32, count: u32) -> Result<WithRaw<Favorites>> {
+        let mut favorites = WithRaw::<Favorites>::from_response(self.favorites(page, count).await?)
+            .await
+            .context(
+                ErrorContext::new("favorites")
+                    .param("page", page)
+                    .param("count", count),
+            )?;
+        if self.config.expand_long_text {
+            self.expand_long_text(favorites.value.favorites.iter_mut().map(|f| &mut f.status))
+                .await;
+        }
+        Ok(favorites)
+    }
+
+    /// Pages of `favorites_typed` until the favorites run out.
+    pub fn favorites_stream(&self, count: u32) -> impl Stream<Item = Result<Favorites>> + '_ {
+        paged_stream(move |page| self.favorites_typed(page, count))
+    }
+
+    /// Statuses the logged in user liked, newest first, pages start at 1.
+    /// Shaped like `favorites_typed`, `favorited_time` is always `None`.
+    /// Truncated texts are expanded when `config.expand_long_text` is set.
+    pub async fn liked_statuses(&self, page: u32, count: u32) -> Result<Favorites> {
+        Ok(self.liked_statuses_with_raw(page, count).await?.value)
+    }
+
+    /// Same as `liked_statuses`, along with the JSON as it was received.
+    pub async fn liked_statuses_with_raw(
+        &self,
+        page: u32,
+        count: u32,
+    ) -> Result<WithRaw<Favorites>> {
+        info!("getting liked statuses, page: {page}");
+        let session = self.app_session()?;
+        let mut params = Self::signed_params_for(&session);
+        params["uid"] = session.uid.into();
+        params["page"] = page.into();
+        params["count"] = count.into();
+        params["mix_media_enable"] = MIX_MEDIA_ENABLE.into();
+
+        let mut liked = self
+            .get_with_raw::<LikedStatuses>(URL_LIKED_STATUSES, &params)
+            .await
+            .context(
+                ErrorContext::new("liked_statuses")
+                    .param("page", page)
+                    .param("count", count),
+            )?
+            .map(Favorites::from);
+        if self.config.expand_long_text {
+            self.expand_long_text(liked.value.favorites.iter_mut().map(|f| &mut f.status))
+                .await;
+        }
+        Ok(liked)
+    }
+
+    /// Pages of `liked_statuses` until the likes run out, like `favorites_stream`.
+    pub fn liked_statuses_stream(&self, count: u32) -> impl Stream<Item = Result<Favorites>> + '_ {
+        paged_stream(move |page| self.liked_statuses(page, count))
     }
 
     pub async fn favorites_destroy(&self, id: i64) -> Result<()> {
         info!("destroying favorite, id: {id}");
-        let session = self.session()?;
-        let s = utils::generate_s(&session.uid, FROM);
-        let mut params = utils::build_common_params();
-        params["gsid"] = session.gsid.clone().into();
-        params["s"] = s.into();
+        let mut params = self.signed_params()?;
         params["id"] = id.into();
         let _ = self
             .client
@@ -49,7 +143,8 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.retry_times,
                 self.config.timeout,
             )
-            .await?;
+            .await
+            .context(ErrorContext::new("favorites_destroy").param("id", id))?;
         debug!("favorite {id} destroyed");
         Ok(())
     }
@@ -69,3 +164,113 @@ mod real_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use futures::TryStreamExt;
+
+    use crate::{
+        error::Error,
+        mock::{MockClient, logged_in_client},
+    };
+
+    #[tokio::test]
+    async fn test_favorites_typed() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_favorites_response_from_file(Path::new("tests/data/favorites.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+        let favorites = weibo_api.favorites_typed(1, 20).await.unwrap();
+
+        assert_eq!(favorites.total_number, 2);
+        let status = &favorites.favorites[0].status;
+        assert!(status.needs_long_text());
+        assert_eq!(
+            status.created_at.unwrap().to_rfc3339(),
+            "2025-07-08T20:15:03+08:00"
+        );
+        let pictures: Vec<_> = status.pictures().iter().map(|p| &p.pic_id).collect();
+        assert_eq!(pictures, ["006pic2", "006pic1"]);
+        let retweet = status.retweeted_status.as_ref().unwrap();
+        assert_eq!(retweet.user.as_ref().unwrap().id, 2222222222);
+        assert_eq!(retweet.reposts_count, 0);
+        assert!(favorites.favorites[1].status.deleted);
+    }
+
+    #[tokio::test]
+    async fn test_favorites_error_context() {
+        let mock_client = MockClient::new();
+        mock_client.set_favorites_response_from_str(r#"{"favorites": "#);
+        let weibo_api = logged_in_client(mock_client);
+        let error = weibo_api.favorites_typed(3, 20).await.unwrap_err();
+
+        assert!(matches!(error.kind(), Error::DeserializationError(_)));
+        let context = error.context().unwrap();
+        assert_eq!(context.endpoint, "favorites");
+        assert_eq!(context.params["page"], "3");
+        assert!(
+            error
+                .to_string()
+                .starts_with("favorites [count=20, page=3]: Failed to deserialize")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_favorites_with_raw() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_favorites_response_from_file(Path::new("tests/data/favorites.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+        let favorites = weibo_api.favorites_with_raw(1, 20).await.unwrap();
+
+        let raw_status = &favorites.raw["favorites"][0]["status"];
+        assert_eq!(favorites.value.favorites[0].status.mid, raw_status["mid"]);
+        assert!(raw_status["pic_ids"].is_array());
+
+        weibo_api.client.set_favorites_response_from_str(
+            r#"{"favorites": [{"status": {"user": {"screen_name": 5}}}]}"#,
+        );
+        let error = weibo_api.favorites_with_raw(1, 20).await.unwrap_err();
+        let Error::InvalidField { path, .. } = error.kind() else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert_eq!(path, "favorites[0].status.user.screen_name");
+
+        weibo_api.client.set_favorites_response_from_str(
+            r#"{"errmsg": "User does not exists!", "errno": 20003, "errtype": "DEFAULT_ERROR", "isblock": false}"#,
+        );
+        let error = weibo_api.favorites_with_raw(1, 20).await.unwrap_err();
+        assert!(matches!(error.kind(), Error::ApiError(e) if e.errno == 20003));
+    }
+
+    #[tokio::test]
+    async fn test_liked_statuses() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_liked_statuses_response_from_file(Path::new("tests/data/liked_statuses.json"))
+            .unwrap();
+        mock_client
+            .set_favorites_response_from_file(Path::new("tests/data/favorites.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+
+        let liked = weibo_api.liked_statuses(1, 20).await.unwrap();
+        assert_eq!(liked.total_number, 2);
+        let ids: Vec<_> = liked.favorites.iter().map(|f| f.status.id).collect();
+        assert_eq!(ids, [5186000000000022, 5186000000000021]);
+        assert!(liked.favorites[0].favorited_time.is_none());
+
+        // Both sources page the same way, one page covers each fixture.
+        let liked: Vec<_> = weibo_api
+            .liked_statuses_stream(20)
+            .try_collect()
+            .await
+            .unwrap();
+        let favorites: Vec<_> = weibo_api.favorites_stream(20).try_collect().await.unwrap();
+        assert_eq!((liked.len(), favorites.len()), (1, 1));
+    }
+}
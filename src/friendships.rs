@@ -0,0 +1,203 @@
+use futures::{Stream, TryStreamExt, stream};
+use log::{info, warn};
+
+use crate::{
+    api_client::ApiClient,
+    constants::urls::{URL_FRIENDSHIPS_FOLLOWERS, URL_FRIENDSHIPS_FRIENDS},
+    error::{ErrorContext, Result, ResultExt},
+    http_client::HttpClient,
+    models::{FollowList, UserPage, WithRaw},
+};
+
+/// Users per page, the most the api returns.
+const PAGE_SIZE: u32 = 50;
+
+#[derive(Debug, Clone, Copy)]
+enum Relation {
+    Friends,
+    Followers,
+}
+
+impl Relation {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Friends => "friends",
+            Self::Followers => "followers",
+        }
+    }
+
+    fn url(self) -> &'static str {
+        match self {
+            Self::Friends => URL_FRIENDSHIPS_FRIENDS,
+            Self::Followers => URL_FRIENDSHIPS_FOLLOWERS,
+        }
+    }
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Users `uid` follows, starting at `cursor`, 0 for the first page.
+    pub async fn friends(&self, uid: i64, cursor: u64) -> Result<UserPage> {
+        Ok(self.friends_with_raw(uid, cursor).await?.value)
+    }
+
+    /// Same as `friends`, along with the JSON as it was received.
+    pub async fn friends_with_raw(&self, uid: i64, cursor: u64) -> Result<WithRaw<UserPage>> {
+        self.relation_page(Relation::Friends, uid, cursor).await
+    }
+
+    /// Users following `uid`, starting at `cursor`, 0 for the first page.
+    pub async fn followers(&self, uid: i64, cursor: u64) -> Result<UserPage> {
+        Ok(self.followers_with_raw(uid, cursor).await?.value)
+    }
+
+    /// Same as `followers`, along with the JSON as it was received.
+    pub async fn followers_with_raw(&self, uid: i64, cursor: u64) -> Result<WithRaw<UserPage>> {
+        self.relation_page(Relation::Followers, uid, cursor).await
+    }
+
+    /// Pages of `friends` until `next_cursor` is 0.
+    pub fn friends_stream(&self, uid: i64) -> impl Stream<Item = Result<UserPage>> + '_ {
+        self.relation_stream(Relation::Friends, uid)
+    }
+
+    /// Pages of `followers` until `next_cursor` is 0.
+    pub fn followers_stream(&self, uid: i64) -> impl Stream<Item = Result<UserPage>> + '_ {
+        self.relation_stream(Relation::Followers, uid)
+    }
+
+    /// All the friends the api lists, see `FollowList::is_partial`.
+    pub async fn friends_all(&self, uid: i64) -> Result<FollowList> {
+        self.relation_all(Relation::Friends, uid).await
+    }
+
+    /// All the followers the api lists, see `FollowList::is_partial`.
+    pub async fn followers_all(&self, uid: i64) -> Result<FollowList> {
+        self.relation_all(Relation::Followers, uid).await
+    }
+
+    async fn relation_page(
+        &self,
+        relation: Relation,
+        uid: i64,
+        cursor: u64,
+    ) -> Result<WithRaw<UserPage>> {
+        info!("getting {}, uid: {uid}, cursor: {cursor}", relation.name());
+        let mut params = self.signed_params()?;
+        params["uid"] = uid.into();
+        params["cursor"] = cursor.into();
+        params["count"] = PAGE_SIZE.into();
+
+        self.get_with_raw(relation.url(), &params).await.context(
+            ErrorContext::new(relation.name())
+                .param("uid", uid)
+                .param("cursor", cursor),
+        )
+    }
+
+    fn relation_stream(
+        &self,
+        relation: Relation,
+        uid: i64,
+    ) -> impl Stream<Item = Result<UserPage>> + '_ {
+        stream::try_unfold(Some(0), move |cursor| async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+            let page = self.relation_page(relation, uid, cursor).await?.value;
+            // An empty page or a cursor going nowhere would loop forever.
+            let next =
+                (page.next_cursor != 0 && page.next_cursor != cursor && !page.users.is_empty())
+                    .then_some(page.next_cursor);
+            Ok(Some((page, next)))
+        })
+    }
+
+    async fn relation_all(&self, relation: Relation, uid: i64) -> Result<FollowList> {
+        let list = self
+            .relation_stream(relation, uid)
+            .try_fold(FollowList::default(), |mut list, page| async move {
+                list.total_number = list.total_number.max(page.total_number);
+                list.users.extend(page.users);
+                Ok(list)
+            })
+            .await?;
+        if list.is_partial() {
+            warn!(
+                "only {} of {} {} of {uid} are accessible",
+                list.accessible(),
+                list.total_number,
+                relation.name()
+            );
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use crate::{api_client::ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_friends() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let _ = weibo_api.friends(1669879400, 0).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::mock::{MockClient, logged_in_client};
+
+    fn api_client() -> ApiClient<MockClient> {
+        logged_in_client(MockClient::new())
+    }
+
+    #[tokio::test]
+    async fn test_followers() {
+        let weibo_api = api_client();
+        weibo_api
+            .client
+            .set_friendships_followers_response_from_file(Path::new(
+                "tests/data/friendships_followers.json",
+            ))
+            .unwrap();
+        let page = weibo_api.followers(1234567890, 0).await.unwrap();
+        assert_eq!(page.users.len(), 2);
+        assert_eq!(page.users[1].screen_name, "follower_two");
+        assert_eq!(page.total_number, 5000);
+        assert_eq!(page.next_cursor, 0);
+
+        let list = weibo_api.followers_all(1234567890).await.unwrap();
+        assert_eq!(list.accessible(), 2);
+        assert!(list.is_partial());
+    }
+
+    #[tokio::test]
+    async fn test_friends_stream() {
+        let weibo_api = api_client();
+        // The mock ignores the cursor, so every page points to the same next one.
+        weibo_api.client.set_friendships_friends_response_from_str(
+            r#"{"users": [{"id": 1, "screen_name": "a"}], "next_cursor": 50, "total_number": 2}"#,
+        );
+        let pages: Vec<_> = weibo_api.friends_stream(1234567890).collect().await;
+        assert_eq!(pages.len(), 2);
+        let list = weibo_api.friends_all(1234567890).await.unwrap();
+        assert_eq!(list.users.len(), 2);
+        assert!(!list.is_partial());
+
+        weibo_api
+            .client
+            .set_friendships_friends_response_from_str(r#"{"users": [], "next_cursor": 50}"#);
+        let pages: Vec<_> = weibo_api.friends_stream(1234567890).collect().await;
+        assert_eq!(pages.len(), 1);
+    }
+}
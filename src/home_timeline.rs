@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+
+use log::{debug, info, warn};
+
+use crate::{
+    api_client::ApiClient,
+    constants::{params::*, urls::URL_FRIENDS_TIMELINE},
+    error::{ErrorContext, Result, ResultExt},
+    http_client::HttpClient,
+    models::{Status, Timeline, WithRaw},
+};
+
+/// Statuses per page when polling, the most the api returns.
+const POLL_PAGE_SIZE: u32 = 100;
+/// Pages fetched per poll when many statuses came in since the last one.
+const POLL_MAX_PAGES: usize = 5;
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Statuses of the logged in user and of the users they follow, newest
+    /// first. Only statuses older than or equal to `max_id` and newer than
+    /// `since_id` are returned, 0 for no bound. Truncated texts are expanded
+    /// when `config.expand_long_text` is set.
+    pub async fn home_timeline(&self, max_id: i64, since_id: i64, count: u32) -> Result<Timeline> {
+        Ok(self
+            .home_timeline_with_raw(max_id, since_id, count)
+            .await?
+            .value)
+    }
+
+    /// Same as `home_timeline`, along with the JSON as it was received.
+    pub async fn home_timeline_with_raw(
+        &self,
+        max_id: i64,
+        since_id: i64,
+        count: u32,
+    ) -> Result<WithRaw<Timeline>> {
+        info!("getting home timeline, max_id: {max_id}, since_id: {since_id}");
+        let mut params = self.signed_params()?;
+        params["max_id"] = max_id.into();
+        params["since_id"] = since_id.into();
+        params["count"] = count.into();
+        params["mix_media_enable"] = MIX_MEDIA_ENABLE.into();
+
+        let mut timeline: WithRaw<Timeline> = self
+            .get_with_raw(URL_FRIENDS_TIMELINE, &params)
+            .await
+            .context(
+                ErrorContext::new("home_timeline")
+                    .param("max_id", max_id)
+                    .param("since_id", since_id),
+            )?;
+        if self.config.expand_long_text {
+            self.expand_long_text(timeline.value.statuses.iter_mut())
+                .await;
+        }
+        Ok(timeline)
+    }
+
+    /// Home timeline statuses newer than the ones returned by the previous
+    /// call, newest first. The first call returns the latest page. At most
+    /// `POLL_MAX_PAGES` pages are fetched, older statuses beyond them are
+    /// skipped with a warning.
+    pub async fn poll_new(&self) -> Result<Vec<Status>> {
+        let (statuses, gap) = self.poll_new_pages().await?;
+        if gap {
+            warn!(
+                "more than {} new statuses in home timeline, older ones are skipped",
+                POLL_MAX_PAGES * POLL_PAGE_SIZE as usize
+            );
+        }
+
+        if let Some(newest) = statuses.iter().map(|status| status.id).max() {
+            let mut newest_id = self
+                .home_newest_id
+                .lock()
+                .expect("home newest id lock failed");
+            *newest_id = Some(newest_id.map_or(newest, |id| id.max(newest)));
+        }
+        debug!("{} new statuses in home timeline", statuses.len());
+        Ok(statuses)
+    }
+
+    /// The statuses for `poll_new`, and whether more new ones were left
+    /// after the last page.
+    async fn poll_new_pages(&self) -> Result<(Vec<Status>, bool)> {
+        let since_id = self.home_newest_id();
+        let mut seen = HashSet::new();
+        let mut statuses = Vec::new();
+        let mut max_id = 0;
+        let mut gap = false;
+        for page in 1..=POLL_MAX_PAGES {
+            let timeline = self
+                .home_timeline(max_id, since_id.unwrap_or_default(), POLL_PAGE_SIZE)
+                .await?;
+            let full = timeline.statuses.len() >= POLL_PAGE_SIZE as usize;
+            let oldest = timeline.statuses.iter().map(|status| status.id).min();
+            statuses.extend(timeline.statuses.into_iter().filter(|status| {
+                since_id.is_none_or(|since_id| status.id > since_id) && seen.insert(status.id)
+            }));
+            match oldest {
+                // Older statuses than the latest page aren't new on the first poll.
+                Some(oldest) if full && since_id.is_some_and(|since_id| oldest > since_id) => {
+                    max_id = oldest - 1;
+                    gap = page == POLL_MAX_PAGES;
+                }
+                _ => break,
+            }
+        }
+        Ok((statuses, gap))
+    }
+
+    /// Newest status `poll_new` has returned, e.g. to persist between runs.
+    pub fn home_newest_id(&self) -> Option<i64> {
+        *self
+            .home_newest_id
+            .lock()
+            .expect("home newest id lock failed")
+    }
+
+    /// Makes `poll_new` only return statuses newer than `id`.
+    pub fn set_home_newest_id(&self, id: Option<i64>) {
+        *self
+            .home_newest_id
+            .lock()
+            .expect("home newest id lock failed") = id;
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use crate::{api_client::ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_home_timeline() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let _ = weibo_api.home_timeline(0, 0, 20).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::mock::{MockClient, logged_in_client};
+
+    fn api_client() -> ApiClient<MockClient> {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_friends_timeline_response_from_file(Path::new("tests/data/friends_timeline.json"))
+            .unwrap();
+        logged_in_client(mock_client)
+    }
+
+    #[tokio::test]
+    async fn test_home_timeline() {
+        let weibo_api = api_client();
+        let timeline = weibo_api.home_timeline(0, 0, 20).await.unwrap();
+        assert_eq!(timeline.statuses.len(), 3);
+        assert_eq!(timeline.since_id, 5186000000000003);
+        assert_eq!(
+            timeline.statuses[0].user.as_ref().unwrap().screen_name,
+            "friend_one"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_new() {
+        let weibo_api = api_client();
+        let ids = |statuses: Vec<Status>| statuses.iter().map(|s| s.id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(weibo_api.poll_new().await.unwrap()),
+            [5186000000000003, 5186000000000002, 5186000000000001]
+        );
+        assert_eq!(weibo_api.home_newest_id(), Some(5186000000000003));
+        // The mock keeps returning the same page, nothing is new anymore.
+        assert!(weibo_api.poll_new().await.unwrap().is_empty());
+
+        weibo_api.set_home_newest_id(Some(5186000000000001));
+        assert_eq!(
+            ids(weibo_api.poll_new().await.unwrap()),
+            [5186000000000003, 5186000000000002]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_new_gap() {
+        let weibo_api = api_client();
+        // Every page is full and newer than the last poll.
+        let statuses: Vec<_> = (0..POLL_PAGE_SIZE as i64)
+            .map(|i| json!({ "id": 5186000000001000 - i, "text": "new" }))
+            .collect();
+        weibo_api
+            .client
+            .set_friends_timeline_response_from_str(&json!({ "statuses": statuses }).to_string());
+        weibo_api.set_home_newest_id(Some(5186000000000001));
+
+        let (statuses, gap) = weibo_api.poll_new_pages().await.unwrap();
+        assert!(gap);
+        assert_eq!(statuses.len(), POLL_PAGE_SIZE as usize);
+
+        // One page short of the limit is no gap.
+        weibo_api.set_home_newest_id(Some(5186000000000950));
+        let (statuses, gap) = weibo_api.poll_new_pages().await.unwrap();
+        assert!(!gap);
+        assert_eq!(statuses.len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_login_resets_poll_new() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_get_sms_code_response_from_file(Path::new("tests/data/get_sms_code.json"))
+            .unwrap();
+        mock_client
+            .set_login_response_from_file(Path::new("tests/data/login.json"))
+            .unwrap();
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        weibo_api.set_home_newest_id(Some(5186000000000003));
+
+        weibo_api
+            .get_sms_code("1234567890".to_string())
+            .await
+            .unwrap();
+        weibo_api.login("123456").await.unwrap();
+        assert_eq!(weibo_api.home_newest_id(), None);
+    }
+}
@@ -1,28 +1,59 @@
 use async_trait::async_trait;
-use std::{sync::Arc, time::Duration};
+use std::{
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt, stream::BoxStream};
 use log::{debug, error, info, trace};
 use reqwest::{
     RequestBuilder,
-    header::{self, HeaderMap, HeaderValue},
+    header::{self, HeaderValue},
 };
+pub use reqwest::{StatusCode, header::HeaderMap};
 pub use reqwest_cookie_store::CookieStore;
 use reqwest_cookie_store::CookieStoreMutex;
 use serde::{Serialize, de::DeserializeOwned};
+use tokio::time;
 use url::{ParseError, Url};
 
-use crate::error::{Error, Result};
+use crate::{
+    api_client::ErrResponse,
+    error::{Error, ErrorContext, Result},
+    secret::{redact_params, redact_url},
+};
+
+/// Body chunks as they arrive, for bodies too large to buffer.
+pub type ByteStream = BoxStream<'static, Result<Bytes>>;
 
 #[async_trait]
 pub trait HttpResponse: Send + Sync + 'static {
+    fn status(&self) -> StatusCode;
+    fn headers(&self) -> &HeaderMap;
+    /// The url after redirects, when known.
+    fn url(&self) -> Option<&Url>;
     async fn json<T: DeserializeOwned>(self) -> Result<T>;
     async fn text(self) -> Result<String>;
     async fn bytes(self) -> Result<Bytes>;
+    fn bytes_stream(self) -> ByteStream;
 }
 
 #[async_trait]
 impl HttpResponse for reqwest::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+
+    fn url(&self) -> Option<&Url> {
+        Some(self.url())
+    }
+
     async fn json<T: DeserializeOwned>(self) -> Result<T> {
         Ok(self.json::<T>().await?)
     }
@@ -34,6 +65,10 @@ impl HttpResponse for reqwest::Response {
     async fn bytes(self) -> Result<Bytes> {
         Ok(self.bytes().await?)
     }
+
+    fn bytes_stream(self) -> ByteStream {
+        self.bytes_stream().map_err(Error::from).boxed()
+    }
 }
 
 #[async_trait]
@@ -53,7 +88,20 @@ pub trait HttpClient: Send + Sync + Clone + 'static {
         retry_times: u8,
         timeout: Duration,
     ) -> Result<Self::Response>;
+    /// GETs `url` as is, from byte `offset` on with a Range request when it
+    /// isn't 0, e.g. to resume media downloads. `timeout` covers the headers
+    /// only, so large bodies can be streamed. Non-2xx responses are errors.
+    async fn get_range(
+        &self,
+        url: &str,
+        offset: u64,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<Self::Response>;
     fn set_cookie(&self, cookie_store: CookieStore) -> Result<()>;
+    fn get_cookie(&self) -> Result<CookieStore>;
+    /// Drops all cookies, e.g. before restoring the ones of `get_cookie`.
+    fn clear_cookie(&self) -> Result<()>;
 }
 
 #[async_trait]
@@ -77,9 +125,24 @@ impl<C: HttpClient> HttpClient for Arc<C> {
     ) -> Result<Self::Response> {
         self.as_ref().post(url, form, retry_times, timeout).await
     }
+    async fn get_range(
+        &self,
+        url: &str,
+        offset: u64,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<Self::Response> {
+        self.as_ref().get_range(url, offset, headers, timeout).await
+    }
     fn set_cookie(&self, cookie_store: CookieStore) -> Result<()> {
         self.as_ref().set_cookie(cookie_store)
     }
+    fn get_cookie(&self) -> Result<CookieStore> {
+        self.as_ref().get_cookie()
+    }
+    fn clear_cookie(&self) -> Result<()> {
+        self.as_ref().clear_cookie()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -107,14 +170,23 @@ impl Client {
         &self.web_client
     }
 
+    /// Web and SSO hosts need the cookie store, the mobile api does not.
+    fn client_for(&self, url: &Url) -> &reqwest::Client {
+        match url.domain() {
+            Some(domain) if is_web_domain(domain) => &self.web_client,
+            _ => &self.main_client,
+        }
+    }
+
     async fn send_request(
         &self,
         request_builder: RequestBuilder,
         retry_times: u8,
         timeout: Duration,
     ) -> Result<reqwest::Response> {
+        let started = Instant::now();
         let mut attempts = 0;
-        loop {
+        let result = loop {
             let result = request_builder
                 .try_clone()
                 .unwrap()
@@ -122,27 +194,74 @@ impl Client {
                 .send()
                 .await;
             match result {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        return Ok(response);
-                    } else {
-                        return Err(Error::NetworkError(
-                            response.error_for_status().err().unwrap(),
-                        ));
-                    }
-                }
+                Ok(response) => break check_status(response).await,
                 Err(e) => {
                     if e.is_timeout() && attempts < retry_times {
                         attempts += 1;
                         continue;
                     }
-                    return Err(e.into());
+                    break Err(e.into());
                 }
             }
-        }
+        };
+        result.map_err(|e| {
+            let url = request_builder
+                .try_clone()
+                .and_then(|builder| builder.build().ok())
+                .map(|request| redact_url(request.url().as_str()))
+                .unwrap_or_default();
+            e.with_context(ErrorContext {
+                url,
+                attempts: Some(attempts + 1),
+                elapsed: started.elapsed(),
+                ..Default::default()
+            })
+        })
     }
 }
 
+/// Keeps the start of error bodies, enough for weibo error JSON or an HTML title.
+const BODY_SNIPPET_LEN: usize = 512;
+
+/// Turns non-2xx responses into `Error::HttpStatus`, keeping what the body says.
+pub(crate) async fn check_status<R: HttpResponse>(response: R) -> Result<R> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let url = response
+        .url()
+        .map(|url| redact_url(url.as_str()))
+        .unwrap_or_default();
+    let mut headers = Box::new(response.headers().clone());
+    headers.remove(header::SET_COOKIE);
+    headers.remove(header::COOKIE);
+    let body = response.text().await.unwrap_or_default();
+    let api_error = serde_json::from_str::<ErrResponse>(&body).ok();
+    let body_snippet = match body.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body,
+    };
+    error!("HTTP {status} from {url}: {body_snippet}");
+    Err(Error::HttpStatus {
+        status,
+        url,
+        headers,
+        body_snippet,
+        api_error,
+    })
+}
+
+pub(crate) fn timed_out() -> Error {
+    io::Error::new(io::ErrorKind::TimedOut, "request timed out").into()
+}
+
+fn is_web_domain(domain: &str) -> bool {
+    ["weibo.com", "sina.com.cn"]
+        .iter()
+        .any(|web| domain == *web || domain.ends_with(&format!(".{web}")))
+}
+
 fn make_main_client() -> Result<reqwest::Client> {
     info!("Creating new http client with default headers");
     let headers = HeaderMap::from_iter([
@@ -202,17 +321,10 @@ impl HttpClient for Client {
         retry_times: u8,
         timeout: Duration,
     ) -> Result<Self::Response> {
-        debug!("Sending GET request to {url}");
-        trace!(
-            "GET request query: {}",
-            serde_json::to_string_pretty(query).unwrap_or_default()
-        );
+        debug!("Sending GET request to {}", redact_url(url));
+        trace!("GET request query: {}", redact_params(query));
         let url = url::Url::parse(url).map_err(|e| Error::DataConversionError(format!("{e}")))?;
-        let client = if url.domain() == Some("weibo.com") {
-            &self.web_client
-        } else {
-            &self.main_client
-        };
+        let client = self.client_for(&url);
 
         let request_builder = client.get(url).query(query);
         self.send_request(request_builder, retry_times, timeout)
@@ -226,22 +338,39 @@ impl HttpClient for Client {
         retry_times: u8,
         timeout: Duration,
     ) -> Result<Self::Response> {
-        debug!("Sending POST request to {url}");
-        trace!(
-            "POST request form: {}",
-            serde_json::to_string_pretty(form).unwrap_or_default()
-        );
+        debug!("Sending POST request to {}", redact_url(url));
+        trace!("POST request form: {}", redact_params(form));
         let url = url::Url::parse(url).map_err(|e| Error::DataConversionError(format!("{e}")))?;
-        let client = if url.domain() == Some("weibo.com") {
-            &self.web_client
-        } else {
-            &self.main_client
-        };
+        let client = self.client_for(&url);
         let request_builder = client.post(url).form(form);
         self.send_request(request_builder, retry_times, timeout)
             .await
     }
 
+    async fn get_range(
+        &self,
+        url: &str,
+        offset: u64,
+        mut headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<Self::Response> {
+        debug!(
+            "Sending GET request to {}, from byte {offset}",
+            redact_url(url)
+        );
+        let url = url::Url::parse(url).map_err(|e| Error::DataConversionError(format!("{e}")))?;
+        if offset > 0 {
+            let range = HeaderValue::try_from(format!("bytes={offset}-"))
+                .map_err(|e| Error::DataConversionError(e.to_string()))?;
+            headers.insert(header::RANGE, range);
+        }
+        let request = self.main_client.get(url).headers(headers);
+        let response = time::timeout(timeout, request.send())
+            .await
+            .map_err(|_| timed_out())??;
+        check_status(response).await
+    }
+
     fn set_cookie(&self, cookie_store: CookieStore) -> Result<()> {
         let mut cookie_store_guard = self.cookie_store.lock().unwrap();
         for cookie in cookie_store.iter_unexpired() {
@@ -258,6 +387,15 @@ impl HttpClient for Client {
 
         Ok(())
     }
+
+    fn get_cookie(&self) -> Result<CookieStore> {
+        Ok(self.cookie_store.lock().unwrap().clone())
+    }
+
+    fn clear_cookie(&self) -> Result<()> {
+        self.cookie_store.lock().unwrap().clear();
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -298,4 +436,140 @@ mod local_tests {
         let payload: TestPayload = response.json().await.unwrap();
         assert_eq!(payload, expected_response);
     }
+
+    #[tokio::test]
+    async fn test_http_client_bytes_stream() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/stream"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "video/mp4")
+                    .set_body_bytes(vec![7u8; 100_000]),
+            )
+            .mount(&server)
+            .await;
+
+        let client = Client::new().unwrap();
+        let url = format!("{}/stream", server.uri());
+        let response = HttpClient::get(&client, &url, &(), 0, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(HttpResponse::status(&response), StatusCode::OK);
+        assert_eq!(
+            HttpResponse::headers(&response)[header::CONTENT_TYPE],
+            "video/mp4"
+        );
+        let chunks: Vec<Bytes> = HttpResponse::bytes_stream(response)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.iter().map(Bytes::len).sum::<usize>(), 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_http_client_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/blocked"))
+            .respond_with(
+                ResponseTemplate::new(418)
+                    .insert_header("x-request-id", "abc")
+                    .set_body_json(serde_json::json!({
+                        "errmsg": "请求过于频繁",
+                        "errno": -100,
+                        "errtype": "DEFAULT_ERROR",
+                        "isblock": true,
+                    })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/unavailable"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("x".repeat(1000)))
+            .mount(&server)
+            .await;
+
+        let client = Client::new().unwrap();
+        let url = format!("{}/blocked?gsid=secret", server.uri());
+        let error = HttpClient::get(&client, &url, &(), 0, Duration::from_secs(30))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::IM_A_TEAPOT));
+        assert_eq!(error.context().unwrap().attempts, Some(1));
+        let error = error.into_kind();
+        let Error::HttpStatus {
+            url,
+            headers,
+            api_error,
+            ..
+        } = error
+        else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert!(!url.contains("secret"));
+        assert_eq!(headers["x-request-id"], "abc");
+        let api_error = api_error.unwrap();
+        assert_eq!(api_error.errno, -100);
+        assert!(api_error.isblock);
+
+        let url = format!("{}/unavailable", server.uri());
+        let error = HttpClient::get(&client, &url, &(), 0, Duration::from_secs(30))
+            .await
+            .unwrap_err()
+            .into_kind();
+        let Error::HttpStatus {
+            status,
+            body_snippet,
+            api_error,
+            ..
+        } = error
+        else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body_snippet.len(), BODY_SNIPPET_LEN + 3);
+        assert!(api_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_client_errors_hide_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(403).insert_header("set-cookie", "SUB=_2A25gsid; path=/"),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let client = Client::new().unwrap();
+        let query = serde_json::json!({"gsid": "_2A25gsid", "s": "a1b2c3d4"});
+        // A status error and a network error.
+        for (path, timeout) in [("/login", 30_000), ("/slow", 50)] {
+            let url = format!("{}{path}", server.uri());
+            let timeout = Duration::from_millis(timeout);
+            let error = HttpClient::get(&client, &url, &query, 0, timeout)
+                .await
+                .unwrap_err();
+            for logged in [format!("{error}"), format!("{error:?}")] {
+                assert!(!logged.contains("_2A25gsid"), "gsid leaked in {logged}");
+                assert!(!logged.contains("a1b2c3d4"), "s leaked in {logged}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_web_domain() {
+        assert!(is_web_domain("weibo.com"));
+        assert!(is_web_domain("passport.weibo.com"));
+        assert!(is_web_domain("login.sina.com.cn"));
+        assert!(!is_web_domain("api.weibo.cn"));
+        assert!(!is_web_domain("notweibo.com"));
+    }
 }
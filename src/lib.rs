@@ -1,16 +1,32 @@
 pub mod api_client;
 pub mod config;
+pub mod emoji;
 pub mod error;
 pub mod http_client;
+pub mod media;
+pub mod models;
 pub mod profile_statuses;
+pub mod qrcode_login;
+pub mod render;
+pub mod schema;
+pub mod search;
+pub mod secret;
 pub mod session;
 pub mod statuses_show;
+pub mod text;
+pub mod trending;
 
+mod attitudes;
 mod constants;
 mod cookie;
-mod emoji;
 mod favorites;
+mod friendships;
+mod home_timeline;
+mod paging;
+mod users;
 mod utils;
+mod video;
+mod web_session;
 
 #[cfg(any(feature = "test-mocks", test))]
 pub mod mock;
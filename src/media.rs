@@ -0,0 +1,447 @@
+//! Streams pictures and videos, e.g. the urls picked by `best_url`, to a
+//! writer or a file.
+use std::{io, path::Path};
+
+use futures::StreamExt;
+use log::{debug, info, warn};
+use reqwest::{
+    StatusCode,
+    header::{self, HeaderMap, HeaderValue},
+};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::{AsyncWrite, AsyncWriteExt},
+    time,
+};
+use url::Url;
+
+use crate::{
+    api_client::ApiClient,
+    error::{Error, ErrorContext, Result, ResultExt},
+    http_client::{HttpClient, HttpResponse, timed_out},
+    secret::redact_url,
+};
+
+/// Hosts which refuse requests without a weibo Referer.
+const REFERER_HOSTS: &[&str] = &["sinaimg.cn", "weibocdn.com", "weibo.com", "sinajs.cn"];
+const WEIBO_REFERER: &str = "https://weibo.com/";
+const FALLBACK_FILENAME: &str = "download";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Download {
+    /// Last path segment of the url after redirects, e.g. `006pic1.jpg`.
+    pub filename: String,
+    pub final_url: String,
+    /// Size of the complete file.
+    pub size: u64,
+    /// Bytes that were already on disk and not downloaded again.
+    pub resumed_from: u64,
+}
+
+struct MediaResponse<R> {
+    response: R,
+    final_url: Url,
+    /// Offset of the first byte of the body.
+    start: u64,
+    total: Option<u64>,
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Streams `url` to `writer`, following redirects.
+    pub async fn download_media<W>(&self, url: &str, writer: &mut W) -> Result<Download>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        info!("downloading media {}", redact_url(url));
+        let media = self
+            .request_media(url, 0)
+            .await
+            .context(media_context("download_media", url))?;
+        self.stream_media(media, writer, 0)
+            .await
+            .context(media_context("download_media", url))
+    }
+
+    /// Streams `url` to `path`. An existing file is taken as a partial
+    /// download and resumed with a Range request, dropped connections and
+    /// timeouts are resumed up to `config.retry_times`.
+    pub async fn download_media_to_file<P: AsRef<Path>>(
+        &self,
+        url: &str,
+        path: P,
+    ) -> Result<Download> {
+        let path = path.as_ref();
+        info!("downloading media {} to {path:?}", redact_url(url));
+        let mut attempts = 0;
+        loop {
+            match self.download_to_file_once(url, path).await {
+                Err(e) if attempts < self.config.retry_times && is_transient(&e) => {
+                    attempts += 1;
+                    warn!("download interrupted, resuming ({attempts}): {e}");
+                }
+                result => {
+                    return result.context(ErrorContext {
+                        attempts: Some(attempts + 1),
+                        ..media_context("download_media_to_file", url)
+                    });
+                }
+            }
+        }
+    }
+
+    async fn download_to_file_once(&self, url: &str, path: &Path) -> Result<Download> {
+        let offset = match fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let media = match self.request_media(url, offset).await {
+            Err(e) if offset > 0 && e.status() == Some(StatusCode::RANGE_NOT_SATISFIABLE) => {
+                if unsatisfied_range_size(&e) == Some(offset) {
+                    debug!("{path:?} is already complete");
+                    let parsed =
+                        Url::parse(url).map_err(|e| Error::DataConversionError(e.to_string()))?;
+                    return Ok(Download {
+                        filename: filename_from_url(&parsed),
+                        final_url: parsed.to_string(),
+                        size: offset,
+                        resumed_from: offset,
+                    });
+                }
+                debug!("range not satisfiable, downloading {path:?} again");
+                fs::remove_file(path).await?;
+                self.request_media(url, 0).await?
+            }
+            result => result?,
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        if media.start == 0 && offset > 0 {
+            debug!("server ignored the range, downloading {path:?} again");
+            file.set_len(0).await?;
+        }
+        self.stream_media(media, &mut file, offset).await
+    }
+
+    async fn request_media(&self, url: &str, offset: u64) -> Result<MediaResponse<C::Response>> {
+        let parsed = Url::parse(url).map_err(|e| Error::DataConversionError(e.to_string()))?;
+        let mut headers = HeaderMap::new();
+        if let Some(referer) = referer_for(&parsed) {
+            headers.insert(header::REFERER, HeaderValue::from_static(referer));
+        }
+        if offset > 0 {
+            debug!("resuming from byte {offset}");
+        }
+        let response = self
+            .client
+            .get_range(url, offset, headers, self.config.timeout)
+            .await?;
+
+        let final_url = response.url().cloned().unwrap_or(parsed.clone());
+        if final_url != parsed {
+            debug!("redirected to {}", redact_url(final_url.as_str()));
+        }
+        let (start, total) = if response.status() == StatusCode::PARTIAL_CONTENT {
+            let content_range = response
+                .headers()
+                .get(header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_content_range)
+                .ok_or_else(|| {
+                    Error::DataConversionError("invalid Content-Range in partial response".into())
+                })?;
+            if content_range.0 != offset {
+                return Err(Error::DataConversionError(format!(
+                    "requested range from {offset}, got {}",
+                    content_range.0
+                )));
+            }
+            content_range
+        } else {
+            let content_length = response
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+            (0, content_length)
+        };
+        Ok(MediaResponse {
+            response,
+            final_url,
+            start,
+            total,
+        })
+    }
+
+    async fn stream_media<W>(
+        &self,
+        media: MediaResponse<C::Response>,
+        writer: &mut W,
+        offset: u64,
+    ) -> Result<Download>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let MediaResponse {
+            response,
+            final_url,
+            start,
+            total,
+        } = media;
+        let mut received = start;
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = time::timeout(self.config.timeout, body.next())
+            .await
+            .map_err(|_| timed_out())?
+        {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            received += chunk.len() as u64;
+        }
+        writer.flush().await?;
+
+        if let Some(expected) = total
+            && expected != received
+        {
+            return Err(Error::IncompleteDownload { expected, received });
+        }
+        debug!("downloaded {} bytes", received - start);
+        Ok(Download {
+            filename: filename_from_url(&final_url),
+            final_url: final_url.to_string(),
+            size: received,
+            resumed_from: offset.min(start),
+        })
+    }
+}
+
+fn referer_for(url: &Url) -> Option<&'static str> {
+    let domain = url.domain()?;
+    REFERER_HOSTS
+        .iter()
+        .any(|host| domain == *host || domain.ends_with(&format!(".{host}")))
+        .then_some(WEIBO_REFERER)
+}
+
+/// `bytes 100-199/1000` to the start offset and the total size, when known.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+/// The size of the file from the `bytes */1000` Content-Range of a 416.
+fn unsatisfied_range_size(error: &Error) -> Option<u64> {
+    let Error::HttpStatus { headers, .. } = error.kind() else {
+        return None;
+    };
+    let value = headers.get(header::CONTENT_RANGE)?.to_str().ok()?;
+    value.strip_prefix("bytes */")?.trim().parse().ok()
+}
+
+fn filename_from_url(url: &Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .map(|segment| {
+            percent_decode(segment)
+                .replace(['/', '\\'], "_")
+                .trim_start_matches('.')
+                .to_string()
+        })
+        .filter(|filename| !filename.is_empty())
+        .unwrap_or_else(|| FALLBACK_FILENAME.to_string())
+}
+
+fn percent_decode(segment: &str) -> String {
+    url::form_urlencoded::parse(format!("x={}", segment.replace('+', "%2B")).as_bytes())
+        .next()
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_else(|| segment.to_string())
+}
+
+fn media_context(endpoint: &str, url: &str) -> ErrorContext {
+    ErrorContext {
+        url: redact_url(url),
+        ..ErrorContext::new(endpoint)
+    }
+}
+
+fn is_transient(error: &Error) -> bool {
+    match error.kind() {
+        Error::NetworkError(e) => e.is_timeout() || e.is_body() || e.is_connect(),
+        Error::HttpStatus { status, .. } => status.is_server_error(),
+        Error::IoError(e) => e.kind() == io::ErrorKind::TimedOut,
+        Error::IncompleteDownload { expected, received } => received < expected,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::{
+        http_client::Client,
+        mock::{MockClient, MockHttpResponse},
+    };
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header as header_is, method, path},
+    };
+
+    fn api_client() -> ApiClient<Client> {
+        ApiClient::new(Client::new().unwrap(), Default::default())
+    }
+
+    #[test]
+    fn test_referer_and_filename() {
+        let url = Url::parse("https://wx1.sinaimg.cn/large/006pic1.jpg").unwrap();
+        assert_eq!(referer_for(&url), Some(WEIBO_REFERER));
+        assert_eq!(filename_from_url(&url), "006pic1.jpg");
+        let url = Url::parse("https://example.com/a%20b.mp4?token=1").unwrap();
+        assert_eq!(referer_for(&url), None);
+        assert_eq!(filename_from_url(&url), "a b.mp4");
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(filename_from_url(&url), FALLBACK_FILENAME);
+        assert_eq!(parse_content_range("bytes 5-10/11"), Some((5, Some(11))));
+        assert_eq!(parse_content_range("bytes 5-10/*"), Some((5, None)));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let timed_out = timed_out().with_context(media_context("download_media", "https://a/b"));
+        assert!(is_transient(&timed_out));
+        let incomplete = Error::IncompleteDownload {
+            expected: 20,
+            received: 11,
+        };
+        assert!(is_transient(
+            &incomplete.with_context(ErrorContext::new("download_media_to_file"))
+        ));
+        assert!(!is_transient(
+            &Error::NotLoggedIn.with_context(ErrorContext::new("download_media"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_download_media_to_file_unsatisfiable_range() {
+        let url = "https://wx1.sinaimg.cn/large/006pic1.jpg";
+        let mock_client = MockClient::new();
+        mock_client.expect_get(url, MockHttpResponse::new(200, "hello world"));
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("006pic1.jpg");
+
+        // A complete file is kept as is.
+        std::fs::write(&file, "hello world").unwrap();
+        let download = weibo_api.download_media_to_file(url, &file).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello world");
+        assert_eq!((download.size, download.resumed_from), (11, 11));
+        assert_eq!(download.filename, "006pic1.jpg");
+
+        // A file which doesn't match the remote one is downloaded again.
+        std::fs::write(&file, "hello world, again").unwrap();
+        let download = weibo_api.download_media_to_file(url, &file).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello world");
+        assert_eq!((download.size, download.resumed_from), (11, 0));
+
+        std::fs::write(&file, "hello").unwrap();
+        let download = weibo_api.download_media_to_file(url, &file).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello world");
+        assert_eq!(download.resumed_from, 5);
+    }
+
+    #[tokio::test]
+    async fn test_download_media_follows_redirects() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/thumbnail/006pic1.jpg"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("location", format!("{}/large/006pic1.jpg", server.uri())),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/large/006pic1.jpg"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello world".to_vec()))
+            .mount(&server)
+            .await;
+
+        let mut body = Vec::new();
+        let download = api_client()
+            .download_media(
+                &format!("{}/thumbnail/006pic1.jpg", server.uri()),
+                &mut body,
+            )
+            .await
+            .unwrap();
+        assert_eq!(body, b"hello world");
+        assert_eq!(download.filename, "006pic1.jpg");
+        assert_eq!(download.size, 11);
+        assert!(download.final_url.ends_with("/large/006pic1.jpg"));
+    }
+
+    #[tokio::test]
+    async fn test_download_media_to_file_resumes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/video.mp4"))
+            .and(header_is("range", "bytes=5-"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("content-range", "bytes 5-10/11")
+                    .set_body_bytes(b" world".to_vec()),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("video.mp4");
+        std::fs::write(&file, "hello").unwrap();
+        let download = api_client()
+            .download_media_to_file(&format!("{}/video.mp4", server.uri()), &file)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello world");
+        assert_eq!(download.resumed_from, 5);
+        assert_eq!(download.size, 11);
+    }
+
+    #[tokio::test]
+    async fn test_download_media_checks_length() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/video.mp4"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("content-range", "bytes 5-19/20")
+                    .set_body_bytes(b" world".to_vec()),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("video.mp4");
+        std::fs::write(&file, "hello").unwrap();
+        let mut weibo_api = api_client();
+        weibo_api.config.retry_times = 0;
+        let error = weibo_api
+            .download_media_to_file(&format!("{}/video.mp4", server.uri()), &file)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            Error::IncompleteDownload {
+                expected: 20,
+                received: 11
+            }
+        ));
+        let context = error.context().unwrap();
+        assert_eq!(context.endpoint, "download_media_to_file");
+        assert_eq!(context.attempts, Some(1));
+    }
+}
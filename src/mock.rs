@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::{StreamExt, stream};
+use reqwest::header::{self, HeaderName, HeaderValue};
 use serde::{Serialize, de::DeserializeOwned};
 
 use std::collections::HashMap;
@@ -7,32 +9,102 @@ use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+use reqwest_cookie_store::CookieStore;
+use url::Url;
+
 use crate::constants::urls::*;
 use crate::error::{Error, Result};
-use crate::http_client::{HttpClient, HttpResponse};
+use crate::http_client::{
+    ByteStream, HeaderMap, HttpClient, HttpResponse, StatusCode, check_status,
+};
+#[cfg(test)]
+use crate::{api_client::ApiClient, session::Session};
 
 #[derive(Debug, Clone)]
 pub struct MockHttpResponse {
-    #[allow(unused)]
     status: u16,
+    headers: HeaderMap,
     body: Bytes,
+    /// The url it was served for.
+    url: Option<Url>,
+    /// `bytes_stream` yields chunks of this size, the whole body at once when 0.
+    chunk_size: usize,
 }
 
 impl MockHttpResponse {
     pub fn new(status: u16, body: &str) -> Self {
+        Self::new_with_bytes(status, Bytes::from(body.to_string()))
+    }
+
+    pub fn new_with_bytes(status: u16, body: Bytes) -> Self {
         Self {
             status,
-            body: Bytes::from(body.to_string()),
+            headers: HeaderMap::new(),
+            body,
+            url: None,
+            chunk_size: 0,
         }
     }
 
-    pub fn new_with_bytes(status: u16, body: Bytes) -> Self {
-        Self { status, body }
+    /// Panics on invalid header names or values, like test fixtures should.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.append(
+            HeaderName::try_from(name).unwrap(),
+            HeaderValue::try_from(value).unwrap(),
+        );
+        self
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// The part of a 200 response from byte `offset` on, as a server
+    /// supporting ranges would send it.
+    fn range_from(self, offset: u64) -> Self {
+        let len = self.body.len() as u64;
+        if offset == 0 || self.status != 200 {
+            return self;
+        }
+        if offset >= len {
+            return Self {
+                body: Bytes::new(),
+                ..self.with_status(416)
+            }
+            .with_header("content-range", &format!("bytes */{len}"));
+        }
+        Self {
+            body: self.body.slice(offset as usize..),
+            ..self.with_status(206)
+        }
+        .with_header(
+            "content-range",
+            &format!("bytes {offset}-{}/{len}", len - 1),
+        )
+    }
+
+    fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self.headers.remove(header::CONTENT_LENGTH);
+        self
     }
 }
 
 #[async_trait]
 impl HttpResponse for MockHttpResponse {
+    fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
     async fn json<T: DeserializeOwned>(self) -> Result<T> {
         serde_json::from_slice(&self.body).map_err(Error::from)
     }
@@ -44,11 +116,25 @@ impl HttpResponse for MockHttpResponse {
     async fn bytes(self) -> Result<Bytes> {
         Ok(self.body)
     }
+
+    fn bytes_stream(self) -> ByteStream {
+        let chunk_size = match self.chunk_size {
+            0 => self.body.len().max(1),
+            chunk_size => chunk_size,
+        };
+        let body = self.body;
+        let chunks: Vec<_> = (0..body.len())
+            .step_by(chunk_size)
+            .map(|start| Ok(body.slice(start..body.len().min(start + chunk_size))))
+            .collect();
+        stream::iter(chunks).boxed()
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct MockClient {
     responses: Arc<Mutex<HashMap<String, MockHttpResponse>>>,
+    cookie_store: Arc<Mutex<CookieStore>>,
 }
 
 impl MockClient {
@@ -66,6 +152,17 @@ impl MockClient {
         responses.insert(url.to_string(), response);
     }
 
+    fn response_for(&self, url: &str) -> Result<MockHttpResponse> {
+        let responses = self.responses.lock().unwrap();
+        let response = responses.get(url).cloned().ok_or_else(|| {
+            Error::DataConversionError(format!("No mock response set for URL: {url}"))
+        })?;
+        Ok(MockHttpResponse {
+            url: Url::parse(url).ok(),
+            ..response
+        })
+    }
+
     fn _expect_get_from_str(&self, url: &str, content: &str) {
         self.expect_get(url, MockHttpResponse::new(200, content));
     }
@@ -102,6 +199,86 @@ impl MockClient {
         self._expect_get_from_file(URL_PROFILE_STATUSES, path)
     }
 
+    pub fn set_users_show_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_USERS_SHOW, content)
+    }
+
+    pub fn set_users_show_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_USERS_SHOW, path)
+    }
+
+    pub fn set_friendships_friends_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_FRIENDSHIPS_FRIENDS, content)
+    }
+
+    pub fn set_friendships_friends_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_FRIENDSHIPS_FRIENDS, path)
+    }
+
+    pub fn set_friendships_followers_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_FRIENDSHIPS_FOLLOWERS, content)
+    }
+
+    pub fn set_friendships_followers_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_FRIENDSHIPS_FOLLOWERS, path)
+    }
+
+    pub fn set_friends_timeline_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_FRIENDS_TIMELINE, content)
+    }
+
+    pub fn set_friends_timeline_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_FRIENDS_TIMELINE, path)
+    }
+
+    pub fn set_search_statuses_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_SEARCH_STATUSES, content)
+    }
+
+    pub fn set_search_statuses_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_SEARCH_STATUSES, path)
+    }
+
+    pub fn set_search_users_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_SEARCH_USERS, content)
+    }
+
+    pub fn set_search_users_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_SEARCH_USERS, path)
+    }
+
+    pub fn set_cardlist_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_CARDLIST, content)
+    }
+
+    pub fn set_cardlist_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_CARDLIST, path)
+    }
+
+    pub fn set_profile_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_PROFILE, content)
+    }
+
+    pub fn set_profile_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_PROFILE, path)
+    }
+
+    pub fn set_attitudes_show_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_ATTITUDES_SHOW, content)
+    }
+
+    pub fn set_attitudes_show_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_ATTITUDES_SHOW, path)
+    }
+
+    pub fn set_liked_statuses_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_LIKED_STATUSES, content)
+    }
+
+    pub fn set_liked_statuses_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_LIKED_STATUSES, path)
+    }
+
     pub fn set_favorites_destroy_response_from_str(&self, content: &str) {
         self._expect_post_from_str(URL_FAVORITES_DESTROY, content)
     }
@@ -110,6 +287,22 @@ impl MockClient {
         self._expect_post_from_file(URL_FAVORITES_DESTROY, path)
     }
 
+    pub fn set_attitudes_create_response_from_str(&self, content: &str) {
+        self._expect_post_from_str(URL_ATTITUDES_CREATE, content)
+    }
+
+    pub fn set_attitudes_create_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_post_from_file(URL_ATTITUDES_CREATE, path)
+    }
+
+    pub fn set_attitudes_destroy_response_from_str(&self, content: &str) {
+        self._expect_post_from_str(URL_ATTITUDES_DESTROY, content)
+    }
+
+    pub fn set_attitudes_destroy_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_post_from_file(URL_ATTITUDES_DESTROY, path)
+    }
+
     pub fn set_get_sms_code_response_from_str(&self, content: &str) {
         self._expect_post_from_str(URL_SEND_CODE, content)
     }
@@ -149,6 +342,54 @@ impl MockClient {
     pub fn set_web_emoticon_response_from_file(&self, path: &Path) -> std::io::Result<()> {
         self._expect_get_from_file(URL_WEB_EMOTICON, path)
     }
+
+    pub fn set_qrcode_image_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_QRCODE_IMAGE, content)
+    }
+
+    pub fn set_qrcode_image_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_QRCODE_IMAGE, path)
+    }
+
+    pub fn set_qrcode_check_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_QRCODE_CHECK, content)
+    }
+
+    pub fn set_qrcode_check_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_QRCODE_CHECK, path)
+    }
+
+    pub fn set_sso_login_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_SSO_LOGIN, content)
+    }
+
+    pub fn set_sso_login_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_SSO_LOGIN, path)
+    }
+
+    pub fn set_web_profile_info_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_WEB_PROFILE_INFO, content)
+    }
+
+    pub fn set_web_profile_info_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_WEB_PROFILE_INFO, path)
+    }
+
+    pub fn set_web_config_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_WEB_CONFIG, content)
+    }
+
+    pub fn set_web_config_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_WEB_CONFIG, path)
+    }
+
+    pub fn set_web_hot_search_response_from_str(&self, content: &str) {
+        self._expect_get_from_str(URL_WEB_HOT_SEARCH, content)
+    }
+
+    pub fn set_web_hot_search_response_from_file(&self, path: &Path) -> std::io::Result<()> {
+        self._expect_get_from_file(URL_WEB_HOT_SEARCH, path)
+    }
 }
 
 #[async_trait]
@@ -162,10 +403,7 @@ impl HttpClient for MockClient {
         _retry_times: u8,
         _timeout: std::time::Duration,
     ) -> Result<Self::Response> {
-        let responses = self.responses.lock().unwrap();
-        responses.get(url).cloned().ok_or_else(|| {
-            Error::DataConversionError(format!("No mock response set for URL: {url}"))
-        })
+        self.response_for(url)
     }
 
     async fn post(
@@ -175,15 +413,52 @@ impl HttpClient for MockClient {
         _retry_times: u8,
         _timeout: std::time::Duration,
     ) -> Result<Self::Response> {
-        let responses = self.responses.lock().unwrap();
-        responses.get(url).cloned().ok_or_else(|| {
-            Error::DataConversionError(format!("No mock response set for URL: {url}"))
-        })
+        self.response_for(url)
+    }
+
+    /// Serves the part of a 200 response from `offset` on, with the status
+    /// and `Content-Range` of a server supporting ranges.
+    async fn get_range(
+        &self,
+        url: &str,
+        offset: u64,
+        _headers: HeaderMap,
+        _timeout: std::time::Duration,
+    ) -> Result<Self::Response> {
+        check_status(self.response_for(url)?.range_from(offset)).await
     }
 
-    fn set_cookie(&self, _cookie_store: reqwest_cookie_store::CookieStore) -> Result<()> {
+    fn set_cookie(&self, cookie_store: CookieStore) -> Result<()> {
+        let mut cookie_store_guard = self.cookie_store.lock().unwrap();
+        for cookie in cookie_store.iter_unexpired() {
+            let domain = cookie.domain().unwrap_or_default();
+            let path = cookie.path().unwrap_or("/");
+            let cookie_url = Url::parse(&format!("https://{domain}{path}"))
+                .map_err(|e| Error::DataConversionError(e.to_string()))?;
+            let _ = cookie_store_guard.insert_raw(cookie, &cookie_url);
+        }
         Ok(())
     }
+
+    fn get_cookie(&self) -> Result<CookieStore> {
+        Ok(self.cookie_store.lock().unwrap().clone())
+    }
+
+    fn clear_cookie(&self) -> Result<()> {
+        self.cookie_store.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+/// An `ApiClient` over `mock_client`, logged in with an app session.
+#[cfg(test)]
+pub(crate) fn logged_in_client(mock_client: MockClient) -> ApiClient<MockClient> {
+    let session = Session {
+        gsid: "gsid".into(),
+        uid: "1234567890".to_string(),
+        ..Default::default()
+    };
+    ApiClient::from_session(mock_client, session)
 }
 
 #[cfg(test)]
@@ -220,6 +495,24 @@ mod local_tests {
         assert_eq!(received_data, expected_data);
     }
 
+    #[tokio::test]
+    async fn test_mock_response_bytes_stream() {
+        let response = MockHttpResponse::new(206, "hello world")
+            .with_header("content-range", "bytes 0-10/11")
+            .with_chunk_size(4);
+        assert_eq!(HttpResponse::status(&response), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()["content-range"], "bytes 0-10/11");
+        let chunks: Vec<_> = response
+            .bytes_stream()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks, ["hell", "o wo", "rld"]);
+
+        let unchunked = MockHttpResponse::new(200, "hello world");
+        assert_eq!(unchunked.bytes_stream().count().await, 1);
+    }
+
     #[tokio::test]
     async fn test_mock_client_post_no_response() {
         let mock_client = MockClient::new();
@@ -335,4 +628,148 @@ mod local_tests {
         URL_EMOJI_UPDATE,
         false
     );
+
+    test_setter!(
+        test_set_qrcode_image,
+        set_qrcode_image_response_from_str,
+        set_qrcode_image_response_from_file,
+        URL_QRCODE_IMAGE,
+        true
+    );
+
+    test_setter!(
+        test_set_qrcode_check,
+        set_qrcode_check_response_from_str,
+        set_qrcode_check_response_from_file,
+        URL_QRCODE_CHECK,
+        true
+    );
+
+    test_setter!(
+        test_set_sso_login,
+        set_sso_login_response_from_str,
+        set_sso_login_response_from_file,
+        URL_SSO_LOGIN,
+        true
+    );
+
+    test_setter!(
+        test_set_web_profile_info,
+        set_web_profile_info_response_from_str,
+        set_web_profile_info_response_from_file,
+        URL_WEB_PROFILE_INFO,
+        true
+    );
+
+    test_setter!(
+        test_set_web_config,
+        set_web_config_response_from_str,
+        set_web_config_response_from_file,
+        URL_WEB_CONFIG,
+        true
+    );
+
+    test_setter!(
+        test_set_users_show,
+        set_users_show_response_from_str,
+        set_users_show_response_from_file,
+        URL_USERS_SHOW,
+        true
+    );
+
+    test_setter!(
+        test_set_friendships_friends,
+        set_friendships_friends_response_from_str,
+        set_friendships_friends_response_from_file,
+        URL_FRIENDSHIPS_FRIENDS,
+        true
+    );
+
+    test_setter!(
+        test_set_friendships_followers,
+        set_friendships_followers_response_from_str,
+        set_friendships_followers_response_from_file,
+        URL_FRIENDSHIPS_FOLLOWERS,
+        true
+    );
+
+    test_setter!(
+        test_set_friends_timeline,
+        set_friends_timeline_response_from_str,
+        set_friends_timeline_response_from_file,
+        URL_FRIENDS_TIMELINE,
+        true
+    );
+
+    test_setter!(
+        test_set_search_statuses,
+        set_search_statuses_response_from_str,
+        set_search_statuses_response_from_file,
+        URL_SEARCH_STATUSES,
+        true
+    );
+
+    test_setter!(
+        test_set_search_users,
+        set_search_users_response_from_str,
+        set_search_users_response_from_file,
+        URL_SEARCH_USERS,
+        true
+    );
+
+    test_setter!(
+        test_set_cardlist,
+        set_cardlist_response_from_str,
+        set_cardlist_response_from_file,
+        URL_CARDLIST,
+        true
+    );
+
+    test_setter!(
+        test_set_web_hot_search,
+        set_web_hot_search_response_from_str,
+        set_web_hot_search_response_from_file,
+        URL_WEB_HOT_SEARCH,
+        true
+    );
+
+    test_setter!(
+        test_set_profile,
+        set_profile_response_from_str,
+        set_profile_response_from_file,
+        URL_PROFILE,
+        true
+    );
+
+    test_setter!(
+        test_set_attitudes_create,
+        set_attitudes_create_response_from_str,
+        set_attitudes_create_response_from_file,
+        URL_ATTITUDES_CREATE,
+        false
+    );
+
+    test_setter!(
+        test_set_attitudes_destroy,
+        set_attitudes_destroy_response_from_str,
+        set_attitudes_destroy_response_from_file,
+        URL_ATTITUDES_DESTROY,
+        false
+    );
+
+    test_setter!(
+        test_set_attitudes_show,
+        set_attitudes_show_response_from_str,
+        set_attitudes_show_response_from_file,
+        URL_ATTITUDES_SHOW,
+        true
+    );
+
+    test_setter!(
+        test_set_liked_statuses,
+        set_liked_statuses_response_from_str,
+        set_liked_statuses_response_from_file,
+        URL_LIKED_STATUSES,
+        true
+    );
 }
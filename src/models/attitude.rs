@@ -0,0 +1,87 @@
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::{User, created_at, lenient_number};
+
+/// A reaction to a status, `Heart` is the plain like.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Option<String>", into = "String")]
+pub enum Attitude {
+    #[default]
+    Heart,
+    Haha,
+    Wow,
+    Sad,
+    Angry,
+    /// Any attitude the api adds later, as sent.
+    Other(String),
+}
+
+impl Attitude {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Heart => "heart",
+            Self::Haha => "haha",
+            Self::Wow => "wow",
+            Self::Sad => "sad",
+            Self::Angry => "angry",
+            Self::Other(attitude) => attitude,
+        }
+    }
+}
+
+impl From<String> for Attitude {
+    fn from(attitude: String) -> Self {
+        match attitude.as_str() {
+            // Older listings leave it out or send `smile` for plain likes.
+            "" | "heart" | "smile" => Self::Heart,
+            "haha" => Self::Haha,
+            "wow" => Self::Wow,
+            "sad" => Self::Sad,
+            "angry" => Self::Angry,
+            _ => Self::Other(attitude),
+        }
+    }
+}
+
+/// `null` is a plain like, like a missing attitude.
+impl From<Option<String>> for Attitude {
+    fn from(attitude: Option<String>) -> Self {
+        attitude.map(Self::from).unwrap_or_default()
+    }
+}
+
+impl From<Attitude> for String {
+    fn from(attitude: Attitude) -> Self {
+        attitude.as_str().to_string()
+    }
+}
+
+impl fmt::Display for Attitude {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Who reacted to a status, and how.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttitudeEntry {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub id: i64,
+    #[serde(default)]
+    pub attitude: Attitude,
+    #[serde(default, with = "created_at")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    pub user: Option<User>,
+}
+
+/// A page of `attitudes_show`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttitudePage {
+    #[serde(default)]
+    pub attitudes: Vec<AttitudeEntry>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
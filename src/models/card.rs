@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Status, lenient_number};
+
+/// The `cards` listings of container endpoints, e.g. profile statuses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CardList {
+    #[serde(default)]
+    pub cards: Vec<Card>,
+    #[serde(default, rename = "cardlistInfo")]
+    pub cardlist_info: CardlistInfo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CardlistInfo {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub card_type: i64,
+    pub mblog: Option<Status>,
+    /// Nested cards of group cards.
+    #[serde(default)]
+    pub card_group: Vec<Card>,
+}
+
+impl CardList {
+    /// Statuses of all cards, nested groups included, in display order.
+    pub fn statuses(&self) -> Vec<&Status> {
+        fn collect<'a>(cards: &'a [Card], statuses: &mut Vec<&'a Status>) {
+            for card in cards {
+                statuses.extend(&card.mblog);
+                collect(&card.card_group, statuses);
+            }
+        }
+        let mut statuses = Vec::new();
+        collect(&self.cards, &mut statuses);
+        statuses
+    }
+
+    pub fn statuses_mut(&mut self) -> Vec<&mut Status> {
+        fn collect<'a>(cards: &'a mut [Card], statuses: &mut Vec<&'a mut Status>) {
+            for card in cards {
+                statuses.extend(&mut card.mblog);
+                collect(&mut card.card_group, statuses);
+            }
+        }
+        let mut statuses = Vec::new();
+        collect(&mut self.cards, &mut statuses);
+        statuses
+    }
+}
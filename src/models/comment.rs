@@ -0,0 +1,23 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::{Status, User, created_at, lenient_number};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub id: i64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub rootid: i64,
+    #[serde(default, with = "created_at")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub text: String,
+    pub user: Option<User>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub like_counts: u64,
+    /// The comment this one replies to.
+    pub reply_comment: Option<Box<Comment>>,
+    /// The commented status, only present in some listings.
+    pub status: Option<Box<Status>>,
+}
@@ -0,0 +1,196 @@
+//! `created_at` timestamps, for `#[serde(with = "created_at")]` on
+//! `Option<DateTime<FixedOffset>>` fields.
+//!
+//! The mobile api sends `Tue Jul 15 10:21:33 +0800 2025`, some web responses
+//! send dates without a zone or relative strings like `5分钟前` and `昨天 10:21`.
+//! Zoneless and relative values are taken as Beijing time. Values that can't be
+//! parsed become `None` instead of failing the whole response.
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use log::warn;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+
+/// Format of the mobile api, also used when serializing.
+pub const WEIBO_FORMAT: &str = "%a %b %d %H:%M:%S %z %Y";
+
+pub fn beijing() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+/// Parses relative strings against the current time.
+pub fn parse(s: &str) -> Option<DateTime<FixedOffset>> {
+    parse_relative_to(s, Utc::now().with_timezone(&beijing()))
+}
+
+pub fn parse_relative_to(s: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(datetime) = DateTime::parse_from_str(s, WEIBO_FORMAT) {
+        return Some(datetime);
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime);
+    }
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return from_timestamp(timestamp);
+    }
+    parse_relative(s, now).or_else(|| parse_zoneless(s, now))
+}
+
+fn parse_relative(s: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    if s == "刚刚" {
+        return Some(now);
+    }
+    if let Some(amount) = s.strip_suffix("前") {
+        let units = [
+            ("秒", Duration::seconds(1)),
+            ("分钟", Duration::minutes(1)),
+            ("小时", Duration::hours(1)),
+            ("天", Duration::days(1)),
+        ];
+        for (unit, duration) in units {
+            if let Some(count) = amount.strip_suffix(unit) {
+                let count = count.trim().parse::<i32>().ok()?;
+                return now.checked_sub_signed(duration.checked_mul(count)?);
+            }
+        }
+        return None;
+    }
+    let days_ago = [("今天", 0), ("昨天", 1), ("前天", 2)];
+    for (prefix, days) in days_ago {
+        if let Some(time) = s.strip_prefix(prefix) {
+            let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?;
+            let date = now.date_naive() - Duration::days(days);
+            return beijing().from_local_datetime(&date.and_time(time)).single();
+        }
+    }
+    None
+}
+
+/// `2025-07-15 10:21:33`, `2025-07-15`, and `07-15 10:21` or `07-15` for the current year.
+fn parse_zoneless(s: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let datetime_formats = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+    let naive = datetime_formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .or_else(|| {
+            let with_year = format!("{}-{s}", now.year());
+            datetime_formats
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(&with_year, format).ok())
+                .or_else(|| {
+                    NaiveDate::parse_from_str(&with_year, "%Y-%m-%d")
+                        .ok()
+                        .map(|date| date.and_time(NaiveTime::MIN))
+                })
+        })?;
+    beijing().from_local_datetime(&naive).single()
+}
+
+/// Seconds or, for values too large to be seconds, milliseconds.
+fn from_timestamp(timestamp: i64) -> Option<DateTime<FixedOffset>> {
+    let datetime = if timestamp.abs() >= 100_000_000_000 {
+        DateTime::from_timestamp_millis(timestamp)?
+    } else {
+        DateTime::from_timestamp(timestamp, 0)?
+    };
+    Some(datetime.with_timezone(&beijing()))
+}
+
+pub fn serialize<S>(
+    datetime: &Option<DateTime<FixedOffset>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match datetime {
+        Some(datetime) => serializer.serialize_str(&datetime.format(WEIBO_FORMAT).to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let datetime = match Value::deserialize(deserializer)? {
+        Value::String(s) => {
+            let datetime = parse(&s);
+            if datetime.is_none() && !s.trim().is_empty() {
+                warn!("unrecognized created_at: {s}");
+            }
+            datetime
+        }
+        Value::Number(n) => n.as_i64().and_then(from_timestamp),
+        _ => None,
+    };
+    Ok(datetime)
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2025-07-15T10:21:33+08:00").unwrap()
+    }
+
+    fn parsed(s: &str) -> String {
+        parse_relative_to(s, now()).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_parse_absolute() {
+        assert_eq!(
+            parsed("Tue Jul 15 10:21:33 +0800 2025"),
+            "2025-07-15T10:21:33+08:00"
+        );
+        assert_eq!(parsed("2025-07-15T02:21:33Z"), "2025-07-15T02:21:33+00:00");
+        assert_eq!(parsed("2024-12-31 23:59:59"), "2024-12-31T23:59:59+08:00");
+        assert_eq!(parsed("2024-12-31"), "2024-12-31T00:00:00+08:00");
+        assert_eq!(parsed("07-01 08:00"), "2025-07-01T08:00:00+08:00");
+        assert_eq!(parsed("1752546093"), "2025-07-15T10:21:33+08:00");
+        assert_eq!(parsed("1752546093000"), "2025-07-15T10:21:33+08:00");
+        assert!(parse_relative_to("sometime", now()).is_none());
+    }
+
+    #[test]
+    fn test_parse_relative() {
+        assert_eq!(parsed("刚刚"), "2025-07-15T10:21:33+08:00");
+        assert_eq!(parsed("30秒前"), "2025-07-15T10:21:03+08:00");
+        assert_eq!(parsed("5分钟前"), "2025-07-15T10:16:33+08:00");
+        assert_eq!(parsed("2小时前"), "2025-07-15T08:21:33+08:00");
+        assert_eq!(parsed("今天 09:00"), "2025-07-15T09:00:00+08:00");
+        assert_eq!(parsed("昨天 23:10"), "2025-07-14T23:10:00+08:00");
+        assert!(parse_relative_to("很久前", now()).is_none());
+        assert!(parse_relative_to("2147483647天前", now()).is_none());
+        assert!(parse_relative_to("2147483647秒前", now()).is_some());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Item {
+            #[serde(default, with = "super")]
+            created_at: Option<DateTime<FixedOffset>>,
+        }
+        let item: Item =
+            serde_json::from_str(r#"{ "created_at": "Tue Jul 15 10:21:33 +0800 2025" }"#).unwrap();
+        let json = serde_json::to_string(&item).unwrap();
+        assert_eq!(json, r#"{"created_at":"Tue Jul 15 10:21:33 +0800 2025"}"#);
+        let missing: Item = serde_json::from_str("{}").unwrap();
+        assert_eq!(missing.created_at, None);
+        let invalid: Item = serde_json::from_str(r#"{ "created_at": "?" }"#).unwrap();
+        assert_eq!(invalid.created_at, None);
+    }
+}
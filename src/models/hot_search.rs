@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+use super::{lenient_bool, lenient_number};
+
+/// An entry of the hot search list.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HotSearchEntry {
+    #[serde(default)]
+    pub word: String,
+    /// Position in the list, from 1.
+    #[serde(default, rename = "realpos", deserialize_with = "lenient_number")]
+    pub rank: u32,
+    /// Search heat.
+    #[serde(default, rename = "num", deserialize_with = "lenient_number")]
+    pub heat: u64,
+    /// `新`, `热`, `爆` and the like, empty for most entries.
+    #[serde(default, rename = "label_name")]
+    pub label: String,
+    /// Comma separated, e.g. `社会新闻,民生新闻`.
+    #[serde(default)]
+    pub category: String,
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub is_ad: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotLabel {
+    New,
+    Hot,
+    Boom,
+    Other,
+}
+
+impl HotSearchEntry {
+    pub fn label(&self) -> Option<HotLabel> {
+        match self.label.as_str() {
+            "" => None,
+            "新" => Some(HotLabel::New),
+            "热" => Some(HotLabel::Hot),
+            "爆" => Some(HotLabel::Boom),
+            _ => Some(HotLabel::Other),
+        }
+    }
+
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.category.split(',').filter(|c| !c.is_empty())
+    }
+
+    /// The search page of the topic.
+    pub fn url(&self) -> String {
+        let query: String =
+            form_urlencoded::byte_serialize(format!("#{}#", self.word).as_bytes()).collect();
+        format!("https://s.weibo.com/weibo?q={query}")
+    }
+}
@@ -0,0 +1,276 @@
+//! Pictures of `pic_infos` and the mixed pictures and videos of `mix_media_info`.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Playback, lenient_number};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Picture {
+    #[serde(default)]
+    pub pic_id: String,
+    #[serde(default, rename = "type")]
+    pub kind: PictureKind,
+    pub thumbnail: Option<PictureVariant>,
+    pub bmiddle: Option<PictureVariant>,
+    pub large: Option<PictureVariant>,
+    pub mw2000: Option<PictureVariant>,
+    pub original: Option<PictureVariant>,
+    pub largest: Option<PictureVariant>,
+    /// The motion part of live photos and the mp4 version of gifs.
+    pub video: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PictureKind {
+    #[default]
+    Pic,
+    Gif,
+    #[serde(rename = "livephoto")]
+    LivePhoto,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PictureVariant {
+    #[serde(default)]
+    pub url: String,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub width: u32,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub height: u32,
+}
+
+/// Which variant of a picture or video `best_url` picks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preference {
+    /// The uploaded file.
+    Original,
+    /// The biggest variant available, usually the same file as `Original`.
+    #[default]
+    Largest,
+    /// The smallest variant at least this wide, the widest one otherwise.
+    Width(u32),
+}
+
+/// An entry of `mix_media_info`, statuses mixing pictures and videos.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    content = "data",
+    rename_all = "lowercase",
+    try_from = "RawMediaItem"
+)]
+pub enum MediaItem {
+    #[serde(rename = "pic")]
+    Picture(Picture),
+    Video(VideoMedia),
+    Other,
+}
+
+/// Unknown item types carry data too, which `#[serde(other)]` can't skip.
+#[derive(Deserialize)]
+struct RawMediaItem {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Value,
+}
+
+impl TryFrom<RawMediaItem> for MediaItem {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawMediaItem) -> Result<Self, Self::Error> {
+        Ok(match raw.kind.as_str() {
+            "pic" => Self::Picture(serde_json::from_value(raw.data)?),
+            "video" => Self::Video(serde_json::from_value(raw.data)?),
+            _ => Self::Other,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MixMediaInfo {
+    #[serde(default)]
+    pub items: Vec<MediaItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoMedia {
+    #[serde(default)]
+    pub object_id: String,
+    /// Cover picture.
+    pub page_pic: Option<String>,
+    #[serde(default)]
+    pub media_info: MediaInfo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaInfo {
+    #[serde(default)]
+    pub name: String,
+    pub stream_url: Option<String>,
+    pub stream_url_hd: Option<String>,
+    pub mp4_sd_url: Option<String>,
+    pub mp4_hd_url: Option<String>,
+    pub mp4_720p_mp4: Option<String>,
+    /// Renditions with codec, resolution and bitrate, missing in older payloads.
+    #[serde(default)]
+    pub playback_list: Vec<Playback>,
+    /// Seconds.
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub duration: u32,
+}
+
+impl Picture {
+    fn variants(&self) -> impl Iterator<Item = &PictureVariant> {
+        [
+            &self.thumbnail,
+            &self.bmiddle,
+            &self.large,
+            &self.mw2000,
+            &self.original,
+            &self.largest,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|variant| !variant.url.is_empty())
+    }
+
+    pub fn best_url(&self, preference: Preference) -> Option<&str> {
+        let fallback = || self.variants().last();
+        let variant = match preference {
+            Preference::Original => self
+                .original
+                .as_ref()
+                .filter(|variant| !variant.url.is_empty())
+                .or_else(fallback),
+            Preference::Largest => fallback(),
+            Preference::Width(width) => self
+                .variants()
+                .filter(|variant| variant.width >= width)
+                .min_by_key(|variant| variant.width)
+                .or_else(|| self.variants().max_by_key(|variant| variant.width)),
+        };
+        variant.map(|variant| variant.url.as_str())
+    }
+}
+
+impl VideoMedia {
+    pub fn best_url(&self, preference: Preference) -> Option<&str> {
+        self.media_info.best_url(preference)
+    }
+}
+
+impl MediaInfo {
+    pub fn best_url(&self, preference: Preference) -> Option<&str> {
+        let candidates = self.candidates();
+        let candidate = match preference {
+            Preference::Original | Preference::Largest => candidates.first(),
+            Preference::Width(width) => candidates
+                .iter()
+                .rev()
+                .find(|candidate| candidate.display_width() >= width)
+                .or(candidates.first()),
+        }?;
+        Some(candidate.url)
+    }
+}
+
+impl MediaItem {
+    pub fn best_url(&self, preference: Preference) -> Option<&str> {
+        match self {
+            Self::Picture(picture) => picture.best_url(preference),
+            Self::Video(video) => video.best_url(preference),
+            Self::Other => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn picture() -> Picture {
+        serde_json::from_value(json!({
+            "pic_id": "006pic1",
+            "type": "livephoto",
+            "thumbnail": { "url": "https://wx1.sinaimg.cn/thumbnail/006pic1.jpg", "width": 180, "height": 120 },
+            "bmiddle": { "url": "https://wx1.sinaimg.cn/bmiddle/006pic1.jpg", "width": "440", "height": "293" },
+            "large": { "url": "https://wx1.sinaimg.cn/large/006pic1.jpg", "width": 1080, "height": 720 },
+            "original": { "url": "https://wx1.sinaimg.cn/orj/006pic1.jpg", "width": 4032, "height": 2688 },
+            "largest": { "url": "https://wx1.sinaimg.cn/largest/006pic1.jpg", "width": 4032, "height": 2688 },
+            "video": "https://livephoto.us.sinaimg.cn/006pic1.mov"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_picture_best_url() {
+        let picture = picture();
+        assert_eq!(picture.kind, PictureKind::LivePhoto);
+        assert_eq!(
+            picture.best_url(Preference::Original),
+            Some("https://wx1.sinaimg.cn/orj/006pic1.jpg")
+        );
+        assert_eq!(
+            picture.best_url(Preference::Largest),
+            Some("https://wx1.sinaimg.cn/largest/006pic1.jpg")
+        );
+        assert_eq!(
+            picture.best_url(Preference::Width(400)),
+            Some("https://wx1.sinaimg.cn/bmiddle/006pic1.jpg")
+        );
+        assert_eq!(
+            picture.best_url(Preference::Width(8000)),
+            Some("https://wx1.sinaimg.cn/largest/006pic1.jpg")
+        );
+
+        let thumbnail_only = Picture {
+            thumbnail: picture.thumbnail.clone(),
+            ..Default::default()
+        };
+        assert_eq!(
+            thumbnail_only.best_url(Preference::Original),
+            Some("https://wx1.sinaimg.cn/thumbnail/006pic1.jpg")
+        );
+        assert_eq!(Picture::default().best_url(Preference::Largest), None);
+    }
+
+    #[test]
+    fn test_mix_media_info() {
+        let info: MixMediaInfo = serde_json::from_value(json!({
+            "items": [
+                { "type": "pic", "id": "006pic1", "data": picture() },
+                {
+                    "type": "video",
+                    "id": "1034:5186",
+                    "data": {
+                        "object_id": "1034:5186",
+                        "page_pic": "https://wx3.sinaimg.cn/orj480/cover.jpg",
+                        "media_info": {
+                            "stream_url": "https://f.video.weibocdn.com/sd.mp4",
+                            "mp4_hd_url": "https://f.video.weibocdn.com/hd.mp4",
+                            "duration": "12"
+                        }
+                    }
+                },
+                { "type": "article", "id": "1", "data": {} }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(info.items.len(), 3);
+        assert!(matches!(info.items[0], MediaItem::Picture(_)));
+        assert_eq!(
+            info.items[1].best_url(Preference::Largest),
+            Some("https://f.video.weibocdn.com/hd.mp4")
+        );
+        assert_eq!(
+            info.items[1].best_url(Preference::Width(320)),
+            Some("https://f.video.weibocdn.com/sd.mp4")
+        );
+        assert_eq!(info.items[2], MediaItem::Other);
+    }
+}
@@ -0,0 +1,121 @@
+//! Typed views of api responses. Only the fields this crate uses are modelled,
+//! everything is optional or defaulted so that partial objects still parse.
+pub mod created_at;
+
+mod attitude;
+mod card;
+mod comment;
+mod hot_search;
+mod media;
+mod profile;
+mod search;
+mod status;
+mod user;
+mod video;
+
+pub use attitude::{Attitude, AttitudeEntry, AttitudePage};
+pub use card::{Card, CardList, CardlistInfo};
+pub use comment::Comment;
+pub use hot_search::{HotLabel, HotSearchEntry};
+pub use media::{
+    MediaInfo, MediaItem, MixMediaInfo, Picture, PictureKind, PictureVariant, Preference,
+    VideoMedia,
+};
+pub use profile::ProfileTab;
+pub use search::{StatusSearch, UserSearch};
+pub use status::{Favorite, Favorites, LongText, Status, Timeline};
+pub use user::{FollowList, User, UserPage};
+pub use video::{PageInfo, PlayInfo, Playback, PlaybackMeta, Rendition, VideoInfo};
+
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{
+    api_client::ErrResponse,
+    error::{Error, Result},
+    http_client::HttpResponse,
+};
+
+/// A typed response with the JSON it was parsed from, e.g. for archiving
+/// fields the models don't cover.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WithRaw<T> {
+    pub value: T,
+    pub raw: Value,
+}
+
+impl<T> WithRaw<T> {
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> WithRaw<U> {
+        WithRaw {
+            value: f(self.value),
+            raw: self.raw,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> WithRaw<T> {
+    pub(crate) async fn from_response(response: impl HttpResponse) -> Result<Self> {
+        Self::from_api_value(response.json::<Value>().await?)
+    }
+
+    /// Parses a success body, api error objects become `Error::ApiError`. The
+    /// error object is tried first because typed bodies default all their fields.
+    pub(crate) fn from_api_value(raw: Value) -> Result<Self> {
+        if let Ok(err_res) = ErrResponse::deserialize(&raw) {
+            return Err(Error::ApiError(err_res));
+        }
+        Ok(Self {
+            value: from_value(&raw)?,
+            raw,
+        })
+    }
+}
+
+/// Deserializes `value`, failures tell the path of the offending field, e.g.
+/// `cards[3].mblog.user.id`.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T> {
+    Ok(serde_path_to_error::deserialize(value)?)
+}
+
+/// Numbers that are sometimes sent as strings, e.g. ids and picture sizes.
+/// Fractions are truncated, unparseable strings like `100万+` become the default.
+pub(crate) fn lenient_number<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr + Default + TryFrom<i64> + TryFrom<u64>,
+{
+    let from_float = |f: f64| T::try_from(f as i64).ok();
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|n| T::try_from(n).ok())
+            .or_else(|| n.as_u64().and_then(|n| T::try_from(n).ok()))
+            .or_else(|| n.as_f64().and_then(from_float))
+            .unwrap_or_default(),
+        Value::String(s) => {
+            let s = s.trim();
+            s.parse()
+                .ok()
+                .or_else(|| s.parse::<f64>().ok().and_then(from_float))
+                .unwrap_or_default()
+        }
+        _ => T::default(),
+    })
+}
+
+/// Flags sent as `true`, `1` or `"1"`.
+pub(crate) fn lenient_bool<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(b) => b,
+        Value::Number(n) => n.as_i64().is_some_and(|n| n != 0),
+        Value::String(s) => matches!(s.as_str(), "1" | "true"),
+        _ => false,
+    })
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use crate::profile_statuses::ContainerType;
+
+/// A tab of a profile, e.g. `微博`, `超话` or `相册`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileTab {
+    #[serde(default)]
+    pub title: String,
+    /// `weibo`, `album`, `super_topic` and the like.
+    #[serde(default)]
+    pub tab_type: String,
+    /// Empty for tabs that are web pages rather than lists.
+    #[serde(default, rename = "containerid")]
+    pub container_id: String,
+}
+
+impl ProfileTab {
+    /// The container to pass to `profile_statuses`, `None` for tabs without one.
+    pub fn container_type(&self) -> Option<ContainerType> {
+        (!self.container_id.is_empty())
+            .then(|| ContainerType::from_container_id(&self.container_id))
+    }
+}
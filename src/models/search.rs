@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Status, User, lenient_number};
+
+/// A page of `search_statuses`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusSearch {
+    #[serde(default)]
+    pub statuses: Vec<Status>,
+    /// Matches found, usually more than search pages reach.
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
+
+/// A page of `search_users`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserSearch {
+    #[serde(default)]
+    pub users: Vec<User>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    MediaItem, MixMediaInfo, PageInfo, Picture, User, created_at, lenient_bool, lenient_number,
+};
+use crate::emoji::EmojiCatalog;
+use crate::text::{self, TextSegment, UrlStruct};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub id: i64,
+    #[serde(default)]
+    pub mid: String,
+    pub mblogid: Option<String>,
+    #[serde(default, with = "created_at")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub source: String,
+    /// Missing for deleted statuses, e.g. retweets of removed posts.
+    pub user: Option<User>,
+    #[serde(default, rename = "isLongText", deserialize_with = "lenient_bool")]
+    pub is_long_text: bool,
+    /// Only present in `statuses_show` responses requested with `isGetLongText`.
+    #[serde(rename = "longText")]
+    pub long_text: Option<LongText>,
+    pub retweeted_status: Option<Box<Status>>,
+    #[serde(default)]
+    pub pic_ids: Vec<String>,
+    #[serde(default)]
+    pub pic_infos: BTreeMap<String, Picture>,
+    /// Set instead of `pic_infos` for statuses mixing pictures and videos.
+    pub mix_media_info: Option<MixMediaInfo>,
+    /// Attached video, article or web page card.
+    pub page_info: Option<PageInfo>,
+    #[serde(default)]
+    pub url_struct: Vec<UrlStruct>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub reposts_count: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub comments_count: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub attitudes_count: u64,
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LongText {
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub url_struct: Vec<UrlStruct>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Favorites {
+    #[serde(default)]
+    pub favorites: Vec<Favorite>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub status: Status,
+    #[serde(default, with = "created_at")]
+    pub favorited_time: Option<DateTime<FixedOffset>>,
+}
+
+/// A page of the home timeline, newest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    #[serde(default)]
+    pub statuses: Vec<Status>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub since_id: i64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub max_id: i64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
+
+impl Status {
+    /// The full text when the long text was fetched, the truncated one otherwise.
+    pub fn full_text(&self) -> &str {
+        match &self.long_text {
+            Some(long_text) if !long_text.content.is_empty() => &long_text.content,
+            _ => &self.text,
+        }
+    }
+
+    /// Whether `statuses_show` has to be called to get the full text.
+    pub fn needs_long_text(&self) -> bool {
+        self.is_long_text && self.long_text.is_none()
+    }
+
+    pub fn segments(&self, emojis: Option<&EmojiCatalog>) -> Vec<TextSegment> {
+        let url_structs = match &self.long_text {
+            Some(long_text) if !long_text.url_struct.is_empty() => &long_text.url_struct,
+            _ => &self.url_struct,
+        };
+        text::tokenize(self.full_text(), url_structs, emojis)
+    }
+
+    /// Pictures in display order.
+    pub fn pictures(&self) -> Vec<&Picture> {
+        if self.pic_ids.is_empty() {
+            return self.pic_infos.values().collect();
+        }
+        self.pic_ids
+            .iter()
+            .filter_map(|pic_id| self.pic_infos.get(pic_id))
+            .collect()
+    }
+
+    /// Pictures and videos in display order, from `mix_media_info` when present.
+    pub fn media(&self) -> Vec<MediaItem> {
+        match &self.mix_media_info {
+            Some(info) if !info.items.is_empty() => info.items.clone(),
+            _ => self
+                .pictures()
+                .into_iter()
+                .cloned()
+                .map(MediaItem::Picture)
+                .collect(),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        let id = self.mblogid.clone().unwrap_or_else(|| self.id.to_string());
+        match &self.user {
+            Some(user) => format!("https://weibo.com/{}/{id}", user.id),
+            None => format!("https://m.weibo.cn/status/{id}"),
+        }
+    }
+}
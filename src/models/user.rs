@@ -0,0 +1,68 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::{created_at, lenient_bool, lenient_number};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub id: i64,
+    #[serde(default)]
+    pub screen_name: String,
+    pub profile_image_url: Option<String>,
+    pub avatar_hd: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub followers_count: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub friends_count: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub statuses_count: u64,
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub verified: bool,
+    pub verified_reason: Option<String>,
+    /// When the account was registered.
+    #[serde(default, with = "created_at")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+}
+
+impl User {
+    pub fn profile_url(&self) -> String {
+        format!("https://weibo.com/u/{}", self.id)
+    }
+}
+
+/// A page of `friends` or `followers`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserPage {
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// 0 on the last page.
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub next_cursor: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub previous_cursor: u64,
+    /// All relations of the user, including the ones the api doesn't list.
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub total_number: u64,
+}
+
+/// All pages of `friends` or `followers`. Weibo only lists part of the
+/// relations of other users, `total_number` tells how many there are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FollowList {
+    pub users: Vec<User>,
+    pub total_number: u64,
+}
+
+impl FollowList {
+    pub fn accessible(&self) -> u64 {
+        self.users.len() as u64
+    }
+
+    /// Whether some relations were not listed.
+    pub fn is_partial(&self) -> bool {
+        self.accessible() < self.total_number
+    }
+}
@@ -0,0 +1,223 @@
+//! Video playback data of `page_info.media_info`.
+use std::{cmp::Reverse, collections::HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{MediaInfo, lenient_number};
+
+/// The card attached to a status: a video, an article, a web page...
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageInfo {
+    #[serde(default, rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub object_type: String,
+    #[serde(default)]
+    pub object_id: String,
+    #[serde(default)]
+    pub page_title: String,
+    pub page_url: Option<String>,
+    /// A url, or an object with one in some responses.
+    pub page_pic: Option<Value>,
+    pub media_info: Option<MediaInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playback {
+    #[serde(default)]
+    pub meta: PlaybackMeta,
+    #[serde(default)]
+    pub play_info: PlayInfo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackMeta {
+    #[serde(default)]
+    pub label: String,
+    pub quality_label: Option<String>,
+    pub quality_desc: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub quality_index: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayInfo {
+    #[serde(default)]
+    pub url: String,
+    pub mime: Option<String>,
+    pub video_codecs: Option<String>,
+    pub audio_codecs: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub width: u32,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub height: u32,
+    /// Bits per second.
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub bitrate: u64,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub size: u64,
+}
+
+/// A video with its renditions, best quality first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub object_id: String,
+    pub title: String,
+    pub cover: Option<String>,
+    /// Seconds.
+    pub duration: u32,
+    pub renditions: Vec<Rendition>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rendition {
+    /// `mp4_720p`, `mp4_hd`... as named by weibo.
+    pub label: String,
+    /// Human readable quality, e.g. `720p` or `高清`.
+    pub quality: Option<String>,
+    pub url: String,
+    pub mime: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub bitrate: Option<u64>,
+    pub size: Option<u64>,
+}
+
+/// A playable url of a `MediaInfo`, borrowed to rank renditions without copying.
+pub(crate) struct Candidate<'a> {
+    pub(crate) url: &'a str,
+    label: &'a str,
+    playback: Option<&'a PlayInfo>,
+    meta: Option<&'a PlaybackMeta>,
+}
+
+/// Legacy url fields, with the height they usually have.
+const LEGACY_URLS: &[(&str, u32)] = &[
+    ("mp4_720p_mp4", 720),
+    ("mp4_hd_url", 540),
+    ("stream_url_hd", 540),
+    ("mp4_sd_url", 360),
+    ("stream_url", 360),
+];
+
+impl Candidate<'_> {
+    fn height(&self) -> u32 {
+        match self.playback {
+            Some(play_info) if play_info.height > 0 => play_info.height,
+            _ => LEGACY_URLS
+                .iter()
+                .find(|(label, _)| *label == self.label)
+                .map_or(0, |(_, height)| *height),
+        }
+    }
+
+    /// Width as sent, or 16:9 of the height.
+    pub(crate) fn display_width(&self) -> u32 {
+        match self.playback {
+            Some(play_info) if play_info.width > 0 => play_info.width,
+            _ => self.height() * 16 / 9,
+        }
+    }
+
+    fn bitrate(&self) -> u64 {
+        self.playback.map_or(0, |play_info| play_info.bitrate)
+    }
+
+    fn to_rendition(&self) -> Rendition {
+        let non_zero_u32 = |n: u32| (n > 0).then_some(n);
+        let non_zero_u64 = |n: u64| (n > 0).then_some(n);
+        Rendition {
+            label: self.label.to_string(),
+            quality: self
+                .meta
+                .and_then(|meta| meta.quality_label.clone().or(meta.quality_desc.clone())),
+            url: self.url.to_string(),
+            mime: self.playback.and_then(|p| p.mime.clone()),
+            video_codec: self.playback.and_then(|p| p.video_codecs.clone()),
+            audio_codec: self.playback.and_then(|p| p.audio_codecs.clone()),
+            width: self.playback.and_then(|p| non_zero_u32(p.width)),
+            height: self.playback.and_then(|p| non_zero_u32(p.height)),
+            bitrate: self.playback.and_then(|p| non_zero_u64(p.bitrate)),
+            size: self.playback.and_then(|p| non_zero_u64(p.size)),
+        }
+    }
+}
+
+impl MediaInfo {
+    /// Playable urls, best quality first. Legacy url fields are only used for
+    /// urls missing from `playback_list`.
+    pub(crate) fn candidates(&self) -> Vec<Candidate<'_>> {
+        let mut seen = HashSet::new();
+        let playbacks = self.playback_list.iter().map(|playback| Candidate {
+            url: &playback.play_info.url,
+            label: &playback.meta.label,
+            playback: Some(&playback.play_info),
+            meta: Some(&playback.meta),
+        });
+        let legacy = LEGACY_URLS.iter().filter_map(|(label, _)| {
+            let url = match *label {
+                "mp4_720p_mp4" => &self.mp4_720p_mp4,
+                "mp4_hd_url" => &self.mp4_hd_url,
+                "stream_url_hd" => &self.stream_url_hd,
+                "mp4_sd_url" => &self.mp4_sd_url,
+                _ => &self.stream_url,
+            };
+            Some(Candidate {
+                url: url.as_deref()?,
+                label,
+                playback: None,
+                meta: None,
+            })
+        });
+        let mut candidates: Vec<_> = playbacks
+            .chain(legacy)
+            .filter(|candidate| !candidate.url.is_empty() && seen.insert(candidate.url))
+            .collect();
+        // Stable, so equal qualities keep the playback list order.
+        candidates.sort_by_key(|candidate| Reverse((candidate.height(), candidate.bitrate())));
+        candidates
+    }
+
+    /// Renditions, best quality first.
+    pub fn renditions(&self) -> Vec<Rendition> {
+        self.candidates()
+            .iter()
+            .map(Candidate::to_rendition)
+            .collect()
+    }
+}
+
+impl PageInfo {
+    pub fn is_video(&self) -> bool {
+        self.kind == "video" || self.object_type == "video"
+    }
+
+    /// `None` when the payload carries no playable url.
+    pub fn video_info(&self) -> Option<VideoInfo> {
+        let media_info = self.media_info.as_ref()?;
+        let renditions = media_info.renditions();
+        if renditions.is_empty() {
+            return None;
+        }
+        let cover = match &self.page_pic {
+            Some(Value::String(url)) => Some(url.clone()),
+            Some(Value::Object(pic)) => pic.get("url").and_then(Value::as_str).map(str::to_string),
+            _ => None,
+        };
+        let title = if media_info.name.is_empty() {
+            self.page_title.clone()
+        } else {
+            media_info.name.clone()
+        };
+        Some(VideoInfo {
+            object_id: self.object_id.clone(),
+            title,
+            cover,
+            duration: media_info.duration,
+            renditions,
+        })
+    }
+}
@@ -0,0 +1,147 @@
+//! Streams over listings with numbered pages, e.g. favorites or search results.
+use std::future::Future;
+
+use futures::{Stream, stream};
+
+use crate::{
+    error::Result,
+    models::{AttitudePage, Favorites, StatusSearch, UserSearch},
+};
+
+/// A page of a listing.
+pub(crate) trait Page {
+    fn item_count(&self) -> usize;
+    /// Items in the whole listing, 0 when the api doesn't tell.
+    fn total_number(&self) -> u64;
+}
+
+/// Pages from 1 on, until one is empty or the items fetched reach the total
+/// number.
+pub(crate) fn paged_stream<'a, T, F, Fut>(fetch: F) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: Page + 'a,
+    F: Fn(u32) -> Fut + 'a,
+    Fut: Future<Output = Result<T>> + 'a,
+{
+    paged_stream_until(fetch, |_| false)
+}
+
+/// Same as `paged_stream`, also stopping after a page `is_last` holds for.
+pub(crate) fn paged_stream_until<'a, T, F, Fut, L>(
+    fetch: F,
+    is_last: L,
+) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: Page + 'a,
+    F: Fn(u32) -> Fut + 'a,
+    Fut: Future<Output = Result<T>> + 'a,
+    L: Fn(&T) -> bool + Clone + 'a,
+{
+    stream::try_unfold((1, 0), move |(page, fetched)| {
+        let next = (page > 0).then(|| fetch(page));
+        let is_last = is_last.clone();
+        async move {
+            let Some(next) = next else {
+                return Ok(None);
+            };
+            let items = next.await?;
+            let fetched = fetched + items.item_count() as u64;
+            let total = items.total_number();
+            let done =
+                items.item_count() == 0 || (total > 0 && fetched >= total) || is_last(&items);
+            Ok(Some((items, (if done { 0 } else { page + 1 }, fetched))))
+        }
+    })
+}
+
+impl Page for Favorites {
+    fn item_count(&self) -> usize {
+        self.favorites.len()
+    }
+
+    fn total_number(&self) -> u64 {
+        self.total_number
+    }
+}
+
+impl Page for AttitudePage {
+    fn item_count(&self) -> usize {
+        self.attitudes.len()
+    }
+
+    fn total_number(&self) -> u64 {
+        self.total_number
+    }
+}
+
+impl Page for StatusSearch {
+    fn item_count(&self) -> usize {
+        self.statuses.len()
+    }
+
+    fn total_number(&self) -> u64 {
+        self.total_number
+    }
+}
+
+impl Page for UserSearch {
+    fn item_count(&self) -> usize {
+        self.users.len()
+    }
+
+    fn total_number(&self) -> u64 {
+        self.total_number
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use futures::{StreamExt, TryStreamExt};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct TestPage {
+        items: usize,
+        total: u64,
+    }
+
+    impl Page for TestPage {
+        fn item_count(&self) -> usize {
+            self.items
+        }
+
+        fn total_number(&self) -> u64 {
+            self.total
+        }
+    }
+
+    async fn pages(fetch: impl Fn(u32) -> TestPage) -> Vec<TestPage> {
+        paged_stream(|page| std::future::ready(Ok(fetch(page))))
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_paged_stream() {
+        // Stops once the total is fetched.
+        let fetched = pages(|_| TestPage { items: 2, total: 5 }).await;
+        assert_eq!(fetched.len(), 3);
+
+        // Without a total, stops at the first empty page.
+        let fetched = pages(|page| TestPage {
+            items: if page < 4 { 2 } else { 0 },
+            total: 0,
+        })
+        .await;
+        assert_eq!(fetched.len(), 4);
+
+        // Without a total and empty page, pages on as long as asked to.
+        let fetched = paged_stream(|_| std::future::ready(Ok(TestPage { items: 2, total: 0 })))
+            .take(60)
+            .count()
+            .await;
+        assert_eq!(fetched, 60);
+    }
+}
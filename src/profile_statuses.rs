@@ -1,14 +1,18 @@
 use log::info;
+use serde::Deserialize;
 
 use crate::{
     api_client::ApiClient,
     constants::{params::*, urls::*},
-    error::Result,
+    error::{ErrorContext, Result, ResultExt},
     http_client::HttpClient,
-    utils,
+    models::{CardList, ProfileTab, WithRaw},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+const PROFILE_PREFIX: &str = "230413";
+const PROFILE_WEIBO: &str = "_-_WEIBO_SECOND_PROFILE_WEIBO";
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum ContainerType {
     #[default]
     Normal,
@@ -16,20 +20,53 @@ pub enum ContainerType {
     Picture,
     Video,
     Article,
+    /// Any other container id as is, e.g. from `profile_tabs`.
+    Custom(String),
 }
 
 impl ContainerType {
     pub fn to_container_id(&self, uid: i64) -> String {
-        match self {
-            Self::Normal => format!("230413{uid}_-_WEIBO_SECOND_PROFILE_WEIBO"),
-            Self::Original => format!("230413{uid}_-_WEIBO_SECOND_PROFILE_WEIBO_ORI"),
-            Self::Picture => format!("230413{uid}_-_WEIBO_SECOND_PROFILE_WEIBO_PIC"),
-            Self::Video => format!("230413{uid}_-_WEIBO_SECOND_PROFILE_WEIBO_VIDEO"),
-            Self::Article => format!("230413{uid}_-_WEIBO_SECOND_PROFILE_WEIBO_ARTICAL"),
+        let suffix = match self {
+            Self::Normal => "",
+            Self::Original => "_ORI",
+            Self::Picture => "_PIC",
+            Self::Video => "_VIDEO",
+            Self::Article => "_ARTICAL",
+            Self::Custom(id) => return id.clone(),
+        };
+        format!("{PROFILE_PREFIX}{uid}{PROFILE_WEIBO}{suffix}")
+    }
+
+    /// The typed shortcut for `id` when there is one, `Custom` otherwise.
+    pub fn from_container_id(id: &str) -> Self {
+        let suffix = id
+            .strip_prefix(PROFILE_PREFIX)
+            .and_then(|rest| rest.split_once(PROFILE_WEIBO))
+            .filter(|(uid, _)| !uid.is_empty() && uid.bytes().all(|b| b.is_ascii_digit()))
+            .map(|(_, suffix)| suffix);
+        match suffix {
+            Some("") => Self::Normal,
+            Some("_ORI") => Self::Original,
+            Some("_PIC") => Self::Picture,
+            Some("_VIDEO") => Self::Video,
+            Some("_ARTICAL") => Self::Article,
+            _ => Self::Custom(id.to_string()),
         }
     }
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct ProfileInfo {
+    #[serde(default, rename = "tabsInfo")]
+    tabs_info: TabsInfo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TabsInfo {
+    #[serde(default)]
+    tabs: Vec<ProfileTab>,
+}
+
 impl<C: HttpClient> ApiClient<C> {
     pub async fn profile_statuses(
         &self,
@@ -38,27 +75,134 @@ impl<C: HttpClient> ApiClient<C> {
         container_type: ContainerType,
         count: u32,
     ) -> Result<C::Response> {
+        let query = ContainerQuery::profile(uid, page, &container_type);
+        self.container_statuses(&query, count).await
+    }
+
+    /// Same as `profile_statuses`, parsed into the typed model. Truncated texts
+    /// are expanded when `config.expand_long_text` is set.
+    pub async fn profile_statuses_typed(
+        &self,
+        uid: i64,
+        page: u32,
+        container_type: ContainerType,
+        count: u32,
+    ) -> Result<CardList> {
+        Ok(self
+            .profile_statuses_with_raw(uid, page, container_type, count)
+            .await?
+            .value)
+    }
+
+    /// Same as `profile_statuses_typed`, along with the JSON as it was received.
+    pub async fn profile_statuses_with_raw(
+        &self,
+        uid: i64,
+        page: u32,
+        container_type: ContainerType,
+        count: u32,
+    ) -> Result<WithRaw<CardList>> {
+        let query = ContainerQuery::profile(uid, page, &container_type);
+        self.container_statuses_with_raw(&query, count).await
+    }
+
+    /// The tabs `uid`'s profile shows, in order. Their containers can be
+    /// fetched with `profile_statuses`, see `ProfileTab::container_type`.
+    pub async fn profile_tabs(&self, uid: i64) -> Result<Vec<ProfileTab>> {
+        Ok(self.profile_tabs_with_raw(uid).await?.value)
+    }
+
+    /// Same as `profile_tabs`, along with the JSON as it was received.
+    pub async fn profile_tabs_with_raw(&self, uid: i64) -> Result<WithRaw<Vec<ProfileTab>>> {
+        info!("getting profile tabs, uid: {uid}");
+        let mut params = self.signed_params()?;
+        params["uid"] = uid.into();
+
+        let info: WithRaw<ProfileInfo> = self
+            .get_with_raw(URL_PROFILE, &params)
+            .await
+            .context(ErrorContext::new("profile_tabs").param("uid", uid))?;
+        Ok(info.map(|info| info.tabs_info.tabs))
+    }
+
+    /// Sends the request for a page of the statuses of a container.
+    pub(crate) async fn container_statuses(
+        &self,
+        query: &ContainerQuery,
+        count: u32,
+    ) -> Result<C::Response> {
+        let container_id = &query.container_id;
         info!(
-            "getting profile statuses, uid: {uid}, page: {page}, containerid: {container_type:?}"
+            "getting {}, uid: {:?}, page: {}, containerid: {container_id}",
+            query.endpoint, query.uid, query.page
         );
-        let session = self.session()?;
-        let s = utils::generate_s(&session.uid, FROM);
-        let mut params = utils::build_common_params();
-        params["gsid"] = session.gsid.clone().into();
-        params["s"] = s.into();
-        params["uid"] = uid.into();
-        params["page"] = page.into();
+        let mut params = self.signed_params()?;
+        if let Some(uid) = query.uid {
+            params["uid"] = uid.into();
+        }
+        params["page"] = query.page.into();
         params["count"] = count.into();
         params["mix_media_enable"] = MIX_MEDIA_ENABLE.into();
-        params["containerid"] = container_type.to_container_id(uid).into();
+        params["containerid"] = container_id.as_str().into();
         self.client
             .get(
-                URL_PROFILE_STATUSES,
+                query.url,
                 &params,
                 self.config.retry_times,
                 self.config.timeout,
             )
             .await
+            .context(query.context())
+    }
+
+    /// Same as `container_statuses`, parsed. Truncated texts are expanded
+    /// when `config.expand_long_text` is set.
+    pub(crate) async fn container_statuses_with_raw(
+        &self,
+        query: &ContainerQuery,
+        count: u32,
+    ) -> Result<WithRaw<CardList>> {
+        let response = self.container_statuses(query, count).await?;
+        let mut cards = WithRaw::<CardList>::from_response(response)
+            .await
+            .context(query.context())?;
+        if self.config.expand_long_text {
+            self.expand_long_text(cards.value.statuses_mut()).await;
+        }
+        Ok(cards)
+    }
+}
+
+/// A page of a container, along with where it is fetched from.
+pub(crate) struct ContainerQuery {
+    /// `ApiClient` method, for errors and logs.
+    pub endpoint: &'static str,
+    pub url: &'static str,
+    /// The profile the container belongs to, if any.
+    pub uid: Option<i64>,
+    pub container_id: String,
+    pub page: u32,
+}
+
+impl ContainerQuery {
+    fn profile(uid: i64, page: u32, container_type: &ContainerType) -> Self {
+        Self {
+            endpoint: "profile_statuses",
+            url: URL_PROFILE_STATUSES,
+            uid: Some(uid),
+            container_id: container_type.to_container_id(uid),
+            page,
+        }
+    }
+
+    fn context(&self) -> ErrorContext {
+        let context = ErrorContext::new(self.endpoint)
+            .param("page", self.page)
+            .param("containerid", &self.container_id);
+        match self.uid {
+            Some(uid) => context.param("uid", uid),
+            None => context,
+        }
     }
 }
 
@@ -80,3 +224,109 @@ mod real_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        config::Config,
+        mock::{MockClient, logged_in_client},
+    };
+
+    fn api_client(mock_client: MockClient) -> ApiClient<MockClient> {
+        let mut weibo_api = logged_in_client(mock_client);
+        weibo_api.config = Config {
+            expand_long_text: true,
+            ..Default::default()
+        };
+        weibo_api
+    }
+
+    #[tokio::test]
+    async fn test_profile_statuses_typed_expands_long_text() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_profile_statuses_response_from_file(Path::new("tests/data/profile_statuses.json"))
+            .unwrap();
+        mock_client
+            .set_statuses_show_response_from_file(Path::new("tests/data/statuses_show.json"))
+            .unwrap();
+        let weibo_api = api_client(mock_client);
+
+        let cards = weibo_api
+            .profile_statuses_typed(1234567890, 2, ContainerType::Normal, 20)
+            .await
+            .unwrap();
+        assert_eq!(cards.cardlist_info.total, 3);
+        let statuses = cards.statuses();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].text.ends_with("第二段 & 结尾"));
+        assert!(!statuses[0].needs_long_text());
+        assert_eq!(statuses[1].text, "短微博");
+    }
+
+    #[tokio::test]
+    async fn test_expand_long_text_keeps_truncated_text_on_failure() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_profile_statuses_response_from_file(Path::new("tests/data/profile_statuses.json"))
+            .unwrap();
+        let weibo_api = api_client(mock_client);
+
+        let cards = weibo_api
+            .profile_statuses_typed(1234567890, 2, ContainerType::Normal, 20)
+            .await
+            .unwrap();
+        let statuses = cards.statuses();
+        assert!(statuses[0].text.ends_with("..."));
+        assert!(statuses[0].needs_long_text());
+    }
+
+    #[test]
+    fn test_container_id_round_trip() {
+        for container_type in [
+            ContainerType::Normal,
+            ContainerType::Original,
+            ContainerType::Picture,
+            ContainerType::Video,
+            ContainerType::Article,
+        ] {
+            let id = container_type.to_container_id(1234567890);
+            assert_eq!(ContainerType::from_container_id(&id), container_type);
+        }
+        let custom = ContainerType::from_container_id("1078031234567890");
+        assert_eq!(custom, ContainerType::Custom("1078031234567890".into()));
+        assert_eq!(custom.to_container_id(1), "1078031234567890");
+    }
+
+    #[tokio::test]
+    async fn test_profile_tabs() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_profile_response_from_file(Path::new("tests/data/profile.json"))
+            .unwrap();
+        mock_client
+            .set_profile_statuses_response_from_file(Path::new("tests/data/profile_statuses.json"))
+            .unwrap();
+        let weibo_api = api_client(mock_client);
+
+        let tabs = weibo_api.profile_tabs(1234567890).await.unwrap();
+        let titles: Vec<_> = tabs.iter().map(|tab| tab.title.as_str()).collect();
+        assert_eq!(titles, ["主页", "微博", "超话", "相册", "小店"]);
+        assert_eq!(tabs[1].container_type(), Some(ContainerType::Normal));
+        assert_eq!(
+            tabs[3].container_type(),
+            Some(ContainerType::Custom("1078031234567890".into()))
+        );
+        assert_eq!(tabs[4].container_type(), None);
+
+        let album = tabs[3].container_type().unwrap();
+        let cards = weibo_api
+            .profile_statuses_typed(1234567890, 1, album, 20)
+            .await
+            .unwrap();
+        assert!(!cards.statuses().is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+//! QR code login through the sina SSO service.
+//!
+//! The flow is the one used by the weibo.com login page: request a QR code,
+//! poll its status until the user confirms the login in the Weibo app, then
+//! exchange the returned `alt` ticket for the web cookies. The resulting
+//! session only carries web cookies, it has no `gsid`.
+use std::time::Duration;
+
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    api_client::{ApiClient, ErrResponse, LoginState},
+    constants::{
+        params::{QRCODE_ENTRY, WEB_DOMAIN},
+        urls::{URL_QRCODE_CHECK, URL_QRCODE_IMAGE, URL_SSO_LOGIN},
+    },
+    error::{Error, ErrorContext, Result, ResultExt},
+    http_client::{HttpClient, HttpResponse},
+    secret::redact_url,
+    session::Session,
+};
+
+const RETCODE_SUCC: i64 = 20000000;
+const RETCODE_WAITING: i64 = 50114001;
+const RETCODE_SCANNED: i64 = 50114002;
+const RETCODE_EXPIRED: i64 = 50114003;
+const RETCODE_USED: i64 = 50114004;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginQrCode {
+    pub qrid: String,
+    /// The text encoded in the QR code, which the Weibo app opens after scanning.
+    pub content: String,
+    /// Weibo's own rendering of the QR code.
+    pub image_url: String,
+}
+
+impl LoginQrCode {
+    /// Renders the QR code with unicode half blocks, suitable for a terminal.
+    pub fn to_ascii(&self) -> Result<String> {
+        use qrcode::render::unicode::Dense1x2;
+
+        let code = self.encode()?;
+        Ok(code.render::<Dense1x2>().build())
+    }
+
+    /// Renders the QR code as a grayscale PNG, each module `module_size` pixels wide.
+    pub fn to_png(&self, module_size: u32) -> Result<Vec<u8>> {
+        const QUIET_ZONE: usize = 4;
+
+        let code = self.encode()?;
+        let module_size = module_size.max(1) as usize;
+        let modules = code.width();
+        let side = (modules + 2 * QUIET_ZONE) * module_size;
+        let colors = code.to_colors();
+        let mut pixels = vec![u8::MAX; side * side];
+        for (i, color) in colors.iter().enumerate() {
+            if *color == qrcode::Color::Light {
+                continue;
+            }
+            let left = (i % modules + QUIET_ZONE) * module_size;
+            let top = (i / modules + QUIET_ZONE) * module_size;
+            for row in top..top + module_size {
+                pixels[row * side + left..row * side + left + module_size].fill(0);
+            }
+        }
+
+        let mut png_bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png_bytes, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| Error::DataConversionError(e.to_string()))?;
+        Ok(png_bytes)
+    }
+
+    fn encode(&self) -> Result<qrcode::QrCode> {
+        qrcode::QrCode::new(self.content.as_bytes())
+            .map_err(|e| Error::DataConversionError(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrCodeStatus {
+    Waiting,
+    Scanned,
+    Confirmed,
+    Expired,
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Requests a new login QR code, to be polled with `check_qrcode`.
+    pub async fn get_login_qrcode(&self) -> Result<LoginQrCode> {
+        info!("getting login qrcode");
+        if !self.login_state().is_init() {
+            warn!("get_login_qrcode called not in init state");
+        }
+
+        let params = json!({
+            "entry": QRCODE_ENTRY,
+            "size": 180,
+        });
+        let response = self
+            .client
+            .get(
+                URL_QRCODE_IMAGE,
+                &params,
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context("get_login_qrcode")?;
+        let response = response.json::<QrCodeResponse<QrCodeImage>>().await?;
+        let image = match (response.retcode, response.data) {
+            (RETCODE_SUCC, Some(image)) => image,
+            (retcode, _) => {
+                error!("failed to get login qrcode: {retcode} {}", response.msg);
+                return Err(Error::ApiError(qrcode_error(retcode, response.msg)));
+            }
+        };
+
+        let qrcode = LoginQrCode {
+            content: qrcode_content(&image.image),
+            qrid: image.qrid,
+            image_url: image.image,
+        };
+        debug!("got login qrcode {}", qrcode.qrid);
+        self.set_login_state(LoginState::WaitingForQrCode {
+            qrid: qrcode.qrid.clone(),
+        });
+        Ok(qrcode)
+    }
+
+    /// Polls the QR code once. On `Confirmed` the login is completed and the
+    /// client is logged in; on `Expired` the client goes back to init state.
+    pub async fn check_qrcode(&self) -> Result<QrCodeStatus> {
+        let LoginState::WaitingForQrCode { qrid } = self.login_state() else {
+            error!("check_qrcode called in invalid state");
+            return Err(Error::NotLoggedIn);
+        };
+        debug!("checking login qrcode {qrid}");
+
+        let params = json!({
+            "entry": QRCODE_ENTRY,
+            "qrid": &qrid,
+        });
+        let response = self
+            .client
+            .get(
+                URL_QRCODE_CHECK,
+                &params,
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context(ErrorContext::new("check_qrcode").param("qrid", &qrid))?;
+        let response = response.json::<QrCodeResponse<QrCodeCheck>>().await?;
+        match (response.retcode, response.data) {
+            (RETCODE_WAITING, _) => Ok(QrCodeStatus::Waiting),
+            (RETCODE_SCANNED, _) => Ok(QrCodeStatus::Scanned),
+            (RETCODE_EXPIRED | RETCODE_USED, _) => {
+                info!("login qrcode {qrid} expired");
+                self.set_login_state(LoginState::Init);
+                Ok(QrCodeStatus::Expired)
+            }
+            (RETCODE_SUCC, Some(check)) => {
+                let session = self.sso_login(&check.alt).await?;
+                info!("qrcode login success, user: {}", session.uid);
+                self.set_login_state(LoginState::LoggedIn { session });
+                Ok(QrCodeStatus::Confirmed)
+            }
+            (retcode, _) => {
+                error!("failed to check login qrcode: {retcode} {}", response.msg);
+                Err(Error::ApiError(qrcode_error(retcode, response.msg)))
+            }
+        }
+    }
+
+    /// Polls the QR code every `interval` until it is confirmed or expired.
+    pub async fn wait_for_qrcode_login(&self, interval: Duration) -> Result<QrCodeStatus> {
+        loop {
+            match self.check_qrcode().await? {
+                status @ (QrCodeStatus::Confirmed | QrCodeStatus::Expired) => return Ok(status),
+                status => debug!("login qrcode status: {status:?}"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn sso_login(&self, alt: &str) -> Result<Session> {
+        let params = json!({
+            "entry": QRCODE_ENTRY,
+            "returntype": "TEXT",
+            "crossdomain": 1,
+            "cdult": 3,
+            "domain": WEB_DOMAIN,
+            "alt": alt,
+            "savestate": 30,
+        });
+        let response = self
+            .client
+            .get(
+                URL_SSO_LOGIN,
+                &params,
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context("sso_login")?;
+        let sso = response.json::<SsoLoginResponse>().await?;
+        let uid = match (sso.is_succ(), sso.uid) {
+            (true, Some(uid)) => uid,
+            _ => {
+                error!("sso login failed: {:?} {:?}", sso.retcode, sso.reason);
+                return Err(Error::ApiError(ErrResponse {
+                    errmsg: sso.reason.unwrap_or_default(),
+                    errno: sso
+                        .retcode
+                        .as_str()
+                        .and_then(|c| c.parse().ok())
+                        .unwrap_or(-1),
+                    errtype: "sso".into(),
+                    isblock: false,
+                }));
+            }
+        };
+
+        // Each cross domain url plants the login cookies for one domain.
+        for url in sso.cross_domain_url_list {
+            debug!("visiting cross domain url {}", redact_url(&url));
+            let _ = self
+                .client
+                .get(
+                    &url,
+                    &json!({}),
+                    self.config.retry_times,
+                    self.config.timeout,
+                )
+                .await
+                .map_err(|e| warn!("cross domain login of {} failed: {e}", redact_url(&url)));
+        }
+
+        Ok(Session {
+            gsid: Default::default(),
+            user: self.web_user(&uid).await?,
+            uid,
+            cookie_store: self.client.get_cookie()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct QrCodeResponse<T> {
+    retcode: i64,
+    #[serde(default)]
+    msg: String,
+    data: Option<T>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct QrCodeImage {
+    qrid: String,
+    image: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct QrCodeCheck {
+    alt: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SsoLoginResponse {
+    retcode: Value,
+    uid: Option<String>,
+    reason: Option<String>,
+    #[serde(rename = "crossDomainUrlList", default)]
+    cross_domain_url_list: Vec<String>,
+}
+
+impl SsoLoginResponse {
+    fn is_succ(&self) -> bool {
+        self.retcode == "0" || self.retcode == 0
+    }
+}
+
+fn qrcode_error(retcode: i64, msg: String) -> ErrResponse {
+    ErrResponse {
+        errmsg: msg,
+        errno: retcode as i32,
+        errtype: "qrcode".into(),
+        isblock: false,
+    }
+}
+
+/// The image url carries the scanned text in its `data` parameter.
+fn qrcode_content(image_url: &str) -> String {
+    url::Url::parse(image_url)
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .find_map(|(key, value)| (key == "data").then(|| value.into_owned()))
+        })
+        .unwrap_or_else(|| image_url.to_string())
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::mock::MockClient;
+    use reqwest_cookie_store::{CookieStore, RawCookie};
+
+    const QRCODE_IMAGE: &str = "https://v2.qr.weibo.cn/inf/gen?api_key=abc&data=https%3A%2F%2Fpassport.weibo.cn%2Fsignin%2Fqrcode%2Fscan%3Fqr%3DQRID";
+
+    fn mock_qrcode_image(client: &MockClient) {
+        let image_json = json!({
+            "retcode": RETCODE_SUCC,
+            "msg": "succ",
+            "data": { "qrid": "QRID", "image": QRCODE_IMAGE },
+        });
+        client.set_qrcode_image_response_from_str(&image_json.to_string());
+    }
+
+    fn mock_qrcode_check(client: &MockClient, retcode: i64, data: Value) {
+        let check_json = json!({ "retcode": retcode, "msg": "", "data": data });
+        client.set_qrcode_check_response_from_str(&check_json.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_get_login_qrcode() {
+        let mock_client = MockClient::new();
+        mock_qrcode_image(&mock_client);
+
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        let qrcode = weibo_api.get_login_qrcode().await.unwrap();
+
+        assert_eq!(qrcode.qrid, "QRID");
+        assert_eq!(
+            qrcode.content,
+            "https://passport.weibo.cn/signin/qrcode/scan?qr=QRID"
+        );
+        assert!(
+            matches!(weibo_api.login_state(), LoginState::WaitingForQrCode { qrid } if qrid == "QRID")
+        );
+        // Dark modules on the light quiet zone, like the printed code.
+        let ascii = qrcode.to_ascii().unwrap();
+        assert!(ascii.starts_with(' '));
+        assert!(ascii.contains('█'));
+        let png = qrcode.to_png(4).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[tokio::test]
+    async fn test_qrcode_login() {
+        let mock_client = MockClient::new();
+        mock_qrcode_image(&mock_client);
+        let weibo_api = ApiClient::new(mock_client.clone(), Default::default());
+        weibo_api.get_login_qrcode().await.unwrap();
+
+        mock_qrcode_check(&mock_client, RETCODE_WAITING, Value::Null);
+        assert_eq!(
+            weibo_api.check_qrcode().await.unwrap(),
+            QrCodeStatus::Waiting
+        );
+        mock_qrcode_check(&mock_client, RETCODE_SCANNED, Value::Null);
+        assert_eq!(
+            weibo_api.check_qrcode().await.unwrap(),
+            QrCodeStatus::Scanned
+        );
+
+        let cross_domain_url = "https://passport.weibo.com/wbsso/login?ticket=ST";
+        mock_qrcode_check(&mock_client, RETCODE_SUCC, json!({ "alt": "ALT" }));
+        mock_client.set_sso_login_response_from_str(
+            &json!({
+                "retcode": "0",
+                "uid": "1234567890",
+                "nick": "example_name",
+                "crossDomainUrlList": [cross_domain_url],
+            })
+            .to_string(),
+        );
+        mock_client.set_web_profile_info_response_from_str(
+            &json!({
+                "ok": 1,
+                "data": { "user": { "idstr": "1234567890", "screen_name": "example_name" } },
+            })
+            .to_string(),
+        );
+        // The cross domain visit is what sets the cookies on a real client.
+        mock_client.expect_get(
+            cross_domain_url,
+            crate::mock::MockHttpResponse::new(200, ""),
+        );
+        let mut cookie_store = CookieStore::new();
+        let weibo_url = url::Url::parse("https://weibo.com/").unwrap();
+        cookie_store
+            .insert_raw(
+                &RawCookie::parse("SUB=sub; domain=.weibo.com; path=/").unwrap(),
+                &weibo_url,
+            )
+            .unwrap();
+        mock_client.set_cookie(cookie_store).unwrap();
+
+        assert_eq!(
+            weibo_api.check_qrcode().await.unwrap(),
+            QrCodeStatus::Confirmed
+        );
+        let session = weibo_api.session().unwrap();
+        assert_eq!(session.uid, "1234567890");
+        assert!(!session.has_gsid());
+        assert_eq!(session.user["screen_name"], "example_name");
+        assert!(session.cookie_store.get("weibo.com", "/", "SUB").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_qrcode_expired() {
+        let mock_client = MockClient::new();
+        mock_qrcode_image(&mock_client);
+        let weibo_api = ApiClient::new(mock_client.clone(), Default::default());
+        weibo_api.get_login_qrcode().await.unwrap();
+
+        mock_qrcode_check(&mock_client, RETCODE_EXPIRED, Value::Null);
+        let status = weibo_api
+            .wait_for_qrcode_login(Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(status, QrCodeStatus::Expired);
+        assert!(weibo_api.login_state().is_init());
+    }
+
+    #[tokio::test]
+    async fn test_check_qrcode_invalid_state() {
+        let weibo_api = ApiClient::new(MockClient::new(), Default::default());
+        assert!(matches!(
+            weibo_api.check_qrcode().await,
+            Err(Error::NotLoggedIn)
+        ));
+    }
+}
@@ -0,0 +1,471 @@
+//! Markdown and standalone HTML renderers for statuses, e.g. to export favorites.
+//!
+//! Output only depends on the status, so rendering the same data twice gives
+//! identical bytes. Call `ApiClient::expand_long_text` first to render full texts.
+use std::fmt::Write;
+
+use crate::{
+    emoji::EmojiCatalog,
+    models::{Preference, Status, User},
+    text::{Link, TextSegment},
+};
+
+/// In the offset Weibo sent, not the local one, to keep output stable across machines.
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const DOCUMENT_STYLE: &str = "body{max-width:40em;margin:2em auto;padding:0 1em;font-family:sans-serif;line-height:1.6}\
+article{border-bottom:1px solid #ddd;padding:1em 0}\
+blockquote{margin:1em 0;padding:0 1em;border-left:3px solid #ccc;color:#444}\
+.pictures img{max-width:100%;margin:.25em 0;display:block}\
+img.emoticon{height:1.2em;vertical-align:middle}";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Renderer<'a> {
+    emojis: Option<&'a EmojiCatalog>,
+    emoticon_images: bool,
+}
+
+impl Default for Renderer<'_> {
+    fn default() -> Self {
+        Self {
+            emojis: None,
+            emoticon_images: true,
+        }
+    }
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Resolves emoticon images, without a catalog every short bracketed
+    /// phrase is kept as alt text.
+    pub fn with_emojis(mut self, emojis: &'a EmojiCatalog) -> Self {
+        self.emojis = Some(emojis);
+        self
+    }
+
+    /// Renders emoticons as their phrase instead of inline images.
+    pub fn emoticon_images(mut self, enabled: bool) -> Self {
+        self.emoticon_images = enabled;
+        self
+    }
+
+    pub fn markdown(&self, status: &Status) -> String {
+        let mut out = self.markdown_body(status);
+        if let Some(retweet) = &status.retweeted_status {
+            let quoted = self.markdown_body(retweet);
+            out.push_str("\n\n");
+            let lines: Vec<_> = quoted
+                .lines()
+                .map(|line| match line {
+                    "" => ">".to_string(),
+                    line => format!("> {line}"),
+                })
+                .collect();
+            out.push_str(&lines.join("\n"));
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Several statuses separated by horizontal rules.
+    pub fn markdown_all(&self, statuses: &[Status]) -> String {
+        let rendered: Vec<_> = statuses
+            .iter()
+            .map(|status| self.markdown(status))
+            .collect();
+        rendered.join("\n---\n\n")
+    }
+
+    /// An `<article>` fragment. All text is escaped and only http(s) urls are
+    /// kept in attributes.
+    pub fn html(&self, status: &Status) -> String {
+        let mut out = String::from("<article class=\"status\">\n");
+        self.html_body(status, &mut out);
+        if let Some(retweet) = &status.retweeted_status {
+            out.push_str("<blockquote class=\"retweet\">\n");
+            self.html_body(retweet, &mut out);
+            out.push_str("</blockquote>\n");
+        }
+        out.push_str("</article>\n");
+        out
+    }
+
+    /// A standalone page. Pictures are loaded without a referrer, the image
+    /// hosts refuse hotlinks from other sites.
+    pub fn html_document(&self, title: &str, statuses: &[Status]) -> String {
+        let mut out = String::from("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n");
+        out.push_str("<meta charset=\"utf-8\">\n");
+        out.push_str("<meta name=\"referrer\" content=\"no-referrer\">\n");
+        let _ = writeln!(out, "<title>{}</title>", escape_html(title));
+        let _ = writeln!(out, "<style>{DOCUMENT_STYLE}</style>");
+        out.push_str("</head>\n<body>\n");
+        for status in statuses {
+            out.push_str(&self.html(status));
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn markdown_body(&self, status: &Status) -> String {
+        let mut header = match &status.user {
+            Some(user) => format!(
+                "**[{}]({})**",
+                escape_markdown(&display_name(user)),
+                markdown_url(&user.profile_url())
+            ),
+            None => "**[deleted]**".to_string(),
+        };
+        if let Some(created_at) = status.created_at {
+            let _ = write!(header, " · {}", created_at.format(TIME_FORMAT));
+        }
+        let _ = write!(header, " · [original]({})", markdown_url(&status.url()));
+
+        let mut blocks = vec![header];
+        blocks.extend(self.paragraphs::<Markdown>(&status.segments(self.emojis)));
+        let pictures: Vec<_> = status
+            .pictures()
+            .into_iter()
+            .filter_map(|picture| picture.best_url(Preference::Largest))
+            .filter(|url| is_http_url(url))
+            .map(|url| format!("![]({})", markdown_url(url)))
+            .collect();
+        if !pictures.is_empty() {
+            blocks.push(pictures.join("\n"));
+        }
+        blocks.join("\n\n")
+    }
+
+    fn html_body(&self, status: &Status, out: &mut String) {
+        out.push_str("<header>");
+        match &status.user {
+            Some(user) => {
+                let _ = write!(
+                    out,
+                    "<a class=\"author\" href=\"{}\">{}</a>",
+                    escape_html(&user.profile_url()),
+                    escape_html(&display_name(user))
+                );
+            }
+            None => out.push_str("<span class=\"author\">[deleted]</span>"),
+        }
+        if let Some(created_at) = status.created_at {
+            let _ = write!(
+                out,
+                " · <time datetime=\"{}\">{}</time>",
+                created_at.to_rfc3339(),
+                created_at.format(TIME_FORMAT)
+            );
+        }
+        let _ = writeln!(
+            out,
+            " · <a href=\"{}\">original</a></header>",
+            escape_html(&status.url())
+        );
+
+        for paragraph in self.paragraphs::<Html>(&status.segments(self.emojis)) {
+            let _ = writeln!(out, "<p>{paragraph}</p>");
+        }
+
+        let pictures: Vec<_> = status
+            .pictures()
+            .into_iter()
+            .filter_map(|picture| picture.best_url(Preference::Largest))
+            .filter(|url| is_http_url(url))
+            .collect();
+        if !pictures.is_empty() {
+            out.push_str("<div class=\"pictures\">\n");
+            for url in pictures {
+                let _ = writeln!(
+                    out,
+                    "<img src=\"{}\" alt=\"\" loading=\"lazy\">",
+                    escape_html(url)
+                );
+            }
+            out.push_str("</div>\n");
+        }
+    }
+
+    /// Single line breaks become hard breaks, blank lines separate paragraphs.
+    fn paragraphs<M: Markup>(&self, segments: &[TextSegment]) -> Vec<String> {
+        let mut paragraphs = Vec::new();
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for segment in segments {
+            match segment {
+                TextSegment::LineBreak => {
+                    let finished = std::mem::take(&mut line);
+                    if finished.trim().is_empty() {
+                        if !lines.is_empty() {
+                            paragraphs.push(lines.join(M::LINE_BREAK));
+                            lines.clear();
+                        }
+                    } else {
+                        lines.push(finished.trim().to_string());
+                    }
+                }
+                segment => line.push_str(&self.inline::<M>(segment)),
+            }
+        }
+        if !line.trim().is_empty() {
+            lines.push(line.trim().to_string());
+        }
+        if !lines.is_empty() {
+            paragraphs.push(lines.join(M::LINE_BREAK));
+        }
+        paragraphs
+    }
+
+    fn inline<M: Markup>(&self, segment: &TextSegment) -> String {
+        match segment {
+            TextSegment::Plain(text) => M::escape(text),
+            TextSegment::LineBreak => M::LINE_BREAK.to_string(),
+            TextSegment::Mention(name) => M::link(
+                Some("mention"),
+                &mention_url(name),
+                &format!("@{}", M::escape(name)),
+            ),
+            TextSegment::Hashtag(topic) => M::link(
+                Some("hashtag"),
+                &hashtag_url(topic),
+                &format!("#{}#", M::escape(topic)),
+            ),
+            TextSegment::Link(link) => {
+                let url = link.resolved_url();
+                let title = M::escape(&link_title(link, &url));
+                if is_http_url(&url) {
+                    M::link(None, &url, &title)
+                } else {
+                    title
+                }
+            }
+            TextSegment::Emoticon(emoticon) => match &emoticon.url {
+                Some(url) if self.emoticon_images && is_http_url(url) => {
+                    M::image("emoticon", url, &M::escape(&emoticon.phrase))
+                }
+                _ => M::escape(&emoticon.phrase),
+            },
+        }
+    }
+}
+
+/// What the markdown and the html output of a text differ in. Texts passed
+/// in are already escaped.
+trait Markup {
+    /// Between the lines of a paragraph.
+    const LINE_BREAK: &'static str;
+    fn escape(text: &str) -> String;
+    /// `class` tells weibo links apart, `None` for external ones.
+    fn link(class: Option<&str>, url: &str, text: &str) -> String;
+    fn image(class: &str, url: &str, alt: &str) -> String;
+}
+
+struct Markdown;
+
+impl Markup for Markdown {
+    const LINE_BREAK: &'static str = "\\\n";
+
+    fn escape(text: &str) -> String {
+        escape_markdown(text)
+    }
+
+    fn link(_class: Option<&str>, url: &str, text: &str) -> String {
+        format!("[{text}]({})", markdown_url(url))
+    }
+
+    fn image(_class: &str, url: &str, alt: &str) -> String {
+        format!("![{alt}]({})", markdown_url(url))
+    }
+}
+
+struct Html;
+
+impl Markup for Html {
+    const LINE_BREAK: &'static str = "<br>\n";
+
+    fn escape(text: &str) -> String {
+        escape_html(text)
+    }
+
+    fn link(class: Option<&str>, url: &str, text: &str) -> String {
+        match class {
+            Some(class) => format!(
+                "<a class=\"{class}\" href=\"{}\">{text}</a>",
+                escape_html(url)
+            ),
+            None => format!(
+                "<a href=\"{}\" rel=\"nofollow noopener noreferrer\">{text}</a>",
+                escape_html(url)
+            ),
+        }
+    }
+
+    fn image(class: &str, url: &str, alt: &str) -> String {
+        format!(
+            "<img class=\"{class}\" src=\"{}\" alt=\"{alt}\">",
+            escape_html(url)
+        )
+    }
+}
+
+fn display_name(user: &User) -> String {
+    if user.screen_name.is_empty() {
+        user.id.to_string()
+    } else {
+        user.screen_name.clone()
+    }
+}
+
+fn mention_url(name: &str) -> String {
+    let mut url = url::Url::parse("https://weibo.com/n/").unwrap();
+    url.path_segments_mut().unwrap().pop().push(name);
+    url.to_string()
+}
+
+fn hashtag_url(topic: &str) -> String {
+    let mut url = url::Url::parse("https://s.weibo.com/weibo").unwrap();
+    url.query_pairs_mut()
+        .append_pair("q", &format!("#{topic}#"));
+    url.to_string()
+}
+
+/// Short links are titled `网页链接`, the resolved url tells more.
+fn link_title(link: &Link, resolved_url: &str) -> String {
+    match &link.title {
+        Some(title) if title != "网页链接" && !title.is_empty() => title.clone(),
+        _ => resolved_url.to_string(),
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+fn markdown_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '~' | '|' | '!'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::{emoji::EmojiSource, models::Favorites};
+    use serde_json::json;
+
+    fn statuses() -> Vec<Status> {
+        let content = std::fs::read_to_string("tests/data/favorites.json").unwrap();
+        let favorites: Favorites = serde_json::from_str(&content).unwrap();
+        let mut statuses: Vec<_> = favorites.favorites.into_iter().map(|f| f.status).collect();
+        let long_text = std::fs::read_to_string("tests/data/statuses_show.json").unwrap();
+        let long_text: Status = serde_json::from_str(&long_text).unwrap();
+        statuses[0].long_text = long_text.long_text;
+        statuses
+    }
+
+    fn catalog() -> EmojiCatalog {
+        let mut catalog = EmojiCatalog::default();
+        catalog.merge_response(
+            &json!([{ "phrase": "[doge]", "url": "https://face.t.sinajs.cn/doge.png" }]),
+            EmojiSource::Web,
+        );
+        catalog
+    }
+
+    #[test]
+    fn test_markdown() {
+        let catalog = catalog();
+        let statuses = statuses();
+        let markdown = Renderer::new().with_emojis(&catalog).markdown(&statuses[0]);
+        let expected = "\
+**[example\\_name](https://weibo.com/u/1234567890)** · 2025-07-08 20:15 · [original](https://weibo.com/1234567890/PzAbCdEfG)
+
+读书笔记 [@示例作者](https://weibo.com/n/%E7%A4%BA%E4%BE%8B%E4%BD%9C%E8%80%85) [#周末读书#](https://s.weibo.com/weibo?q=%23%E5%91%A8%E6%9C%AB%E8%AF%BB%E4%B9%A6%23) ![\\[doge\\]](https://face.t.sinajs.cn/doge.png) 全文 [https://example.com/notes?id=1](https://example.com/notes?id=1)
+
+第二段 & 结尾
+
+![](https://wx2.sinaimg.cn/large/006pic2.jpg)
+![](https://wx1.sinaimg.cn/large/006pic1.jpg)
+
+> **[示例作者](https://weibo.com/u/2222222222)** · 2025-07-07 09:00 · [original](https://weibo.com/2222222222/5185000000000001)
+>
+> 原微博 \\<第一行\\>\\
+> 第二行 \\*重点\\*
+";
+        assert_eq!(markdown, expected);
+
+        let deleted = Renderer::new()
+            .emoticon_images(false)
+            .markdown(&statuses[1]);
+        assert!(deleted.starts_with("**[deleted]**"));
+        assert_eq!(
+            Renderer::new().markdown_all(&statuses),
+            Renderer::new().markdown_all(&statuses)
+        );
+    }
+
+    #[test]
+    fn test_html_is_sanitized() {
+        let status = Status {
+            id: 1,
+            text: "<script>alert(1)</script> [doge] javascript:alert(1)".to_string(),
+            user: Some(User {
+                id: 1,
+                screen_name: "\"><img onerror=x>".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let catalog = catalog();
+        let html = Renderer::new().with_emojis(&catalog).html(&status);
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<img onerror"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains(
+            "<img class=\"emoticon\" src=\"https://face.t.sinajs.cn/doge.png\" alt=\"[doge]\">"
+        ));
+
+        let document = Renderer::new().html_document("收藏 <export>", &statuses());
+        assert!(document.starts_with("<!DOCTYPE html>"));
+        assert!(document.contains("<title>收藏 &lt;export&gt;</title>"));
+        assert!(document.contains("<blockquote class=\"retweet\">"));
+        assert!(document.contains("第一行&gt;<br>\n第二行"));
+        assert!(document.contains("<img src=\"https://wx2.sinaimg.cn/large/006pic2.jpg\""));
+        assert_eq!(
+            document,
+            Renderer::new().html_document("收藏 <export>", &statuses())
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! Field paths, types and frequencies observed in api responses, compared
+//! against a baseline to notice when weibo changes a payload. A port of
+//! `scripts/analyzer.py`.
+//!
+//! The status baseline in `status.json` is recorded from the favorites,
+//! profile statuses and statuses show responses under `tests/data`:
+//!
+//! ```no_run
+//! # async fn run(weibo_api: weibosdk_rs::ApiClient<weibosdk_rs::Client>) -> weibosdk_rs::error::Result<()> {
+//! use weibosdk_rs::schema::Schema;
+//!
+//! let mut schema = Schema::new();
+//! schema.observe_statuses(&weibo_api.favorites_with_raw(1, 20).await?.raw);
+//! let drift = schema.diff(&Schema::status_baseline()?);
+//! if !drift.is_empty() {
+//!     log::warn!("status payload changed:\n{drift}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To record it again, observe a few pages of real responses and save them
+//! over `src/schema/status.json`:
+//!
+//! ```no_run
+//! # async fn run(weibo_api: weibosdk_rs::ApiClient<weibosdk_rs::Client>) -> weibosdk_rs::error::Result<()> {
+//! use weibosdk_rs::schema::Schema;
+//!
+//! let mut baseline = Schema::new();
+//! for page in 1..=5 {
+//!     baseline.observe_statuses(&weibo_api.favorites_with_raw(page, 20).await?.raw);
+//! }
+//! baseline.save("src/schema/status.json")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::Result;
+
+/// Statuses observed in the responses under `tests/data`.
+const STATUS_BASELINE: &str = include_str!("status.json");
+/// Keys whose children are recorded as fields of the parent, so a retweet
+/// counts as one more status.
+const MERGED_KEYS: &[&str] = &["retweeted_status"];
+/// Maps keyed by ids, their values are recorded under `*`.
+const ID_MAP_KEYS: &[&str] = &["pic_infos"];
+/// Types of optional fields which don't count as a retype.
+const EMPTY_TYPES: &[&str] = &["null", "list(empty)"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    /// How many objects were recorded under each prefix, `""` for the
+    /// observed objects themselves.
+    pub objects: BTreeMap<String, u64>,
+    /// Keyed by dotted paths, e.g. `user.screen_name`, `url_struct[].short_url`
+    /// or `pic_infos.*.large.url`.
+    pub fields: BTreeMap<String, FieldStats>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldStats {
+    pub count: u64,
+    /// How often each type was seen, e.g. `string`, `int` or `list(object)`.
+    pub types: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Fields the baseline has never seen.
+    pub new: Vec<String>,
+    /// Fields always present in the baseline but never seen now.
+    pub missing: Vec<String>,
+    pub retyped: Vec<Retyped>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retyped {
+    pub path: String,
+    pub expected: Vec<String>,
+    /// Types seen now which the baseline has never seen.
+    pub found: Vec<String>,
+}
+
+impl Schema {
+    /// An empty schema, to observe responses with.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The checked-in baseline for statuses, for `diff`.
+    pub fn status_baseline() -> Result<Self> {
+        Ok(serde_json::from_str(STATUS_BASELINE)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        info!("Loading schema from {:?}", path.as_ref());
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        info!("Saving schema to {:?}", path.as_ref());
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n")?;
+        Ok(())
+    }
+
+    /// Records the fields of one object, e.g. a status. Anything but an
+    /// object is ignored.
+    pub fn observe(&mut self, value: &Value) {
+        if let Value::Object(object) = value {
+            self.record(object, "");
+        }
+    }
+
+    /// Records the statuses of a `favorites`, `profile_statuses` or
+    /// `statuses_show` response.
+    pub fn observe_statuses(&mut self, response: &Value) {
+        if let Some(favorites) = response["favorites"].as_array() {
+            for favorite in favorites {
+                self.observe(&favorite["status"]);
+            }
+        } else if let Some(cards) = response["cards"].as_array() {
+            let cards = cards.iter().flat_map(|card| {
+                card["card_group"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .chain([card])
+            });
+            for card in cards {
+                self.observe(&card["mblog"]);
+            }
+        } else if response.get("mid").is_some() {
+            self.observe(response);
+        } else {
+            debug!("no statuses found in response");
+        }
+    }
+
+    fn record(&mut self, object: &Map<String, Value>, prefix: &str) {
+        *self.objects.entry(prefix.to_string()).or_default() += 1;
+        for (key, value) in object {
+            let path = match prefix {
+                "" => key.clone(),
+                prefix => format!("{prefix}.{key}"),
+            };
+            let stats = self.fields.entry(path.clone()).or_default();
+            stats.count += 1;
+            *stats.types.entry(type_name(value)).or_default() += 1;
+
+            match value {
+                Value::Object(child) if MERGED_KEYS.contains(&key.as_str()) => {
+                    self.record(child, prefix)
+                }
+                Value::Object(map) if ID_MAP_KEYS.contains(&key.as_str()) => {
+                    for item in map.values() {
+                        if let Value::Object(item) = item {
+                            self.record(item, &format!("{path}.*"));
+                        }
+                    }
+                }
+                Value::Object(child) => self.record(child, &path),
+                Value::Array(items) => {
+                    for item in items {
+                        if let Value::Object(item) = item {
+                            self.record(item, &format!("{path}[]"));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// How many of the objects holding `path` had it, from 0 to 1.
+    pub fn frequency(&self, path: &str) -> Option<f64> {
+        let count = self.fields.get(path)?.count;
+        let objects = self
+            .objects
+            .get(prefix_of(path))
+            .copied()
+            .unwrap_or_default();
+        (objects > 0).then(|| count as f64 / objects as f64)
+    }
+
+    /// Compares what was observed with `baseline`. Fields are only reported
+    /// missing when objects holding them were observed.
+    pub fn diff(&self, baseline: &Schema) -> SchemaDiff {
+        let mut diff = SchemaDiff::default();
+        for (path, stats) in &self.fields {
+            let Some(expected) = baseline.fields.get(path) else {
+                diff.new.push(path.clone());
+                continue;
+            };
+            let found: Vec<_> = stats
+                .types
+                .keys()
+                .filter(|name| {
+                    !EMPTY_TYPES.contains(&name.as_str()) && !expected.types.contains_key(*name)
+                })
+                .cloned()
+                .collect();
+            if !found.is_empty() {
+                diff.retyped.push(Retyped {
+                    path: path.clone(),
+                    expected: expected.types.keys().cloned().collect(),
+                    found,
+                });
+            }
+        }
+        for path in baseline.fields.keys() {
+            let observed = self.objects.contains_key(prefix_of(path));
+            if observed
+                && !self.fields.contains_key(path)
+                && baseline.frequency(path).is_some_and(|f| f >= 1.0)
+            {
+                diff.missing.push(path.clone());
+            }
+        }
+        diff
+    }
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.missing.is_empty() && self.retyped.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.new {
+            writeln!(f, "+ {path}")?;
+        }
+        for path in &self.missing {
+            writeln!(f, "- {path}")?;
+        }
+        for retyped in &self.retyped {
+            writeln!(
+                f,
+                "~ {}: {} -> {}",
+                retyped.path,
+                retyped.expected.join("|"),
+                retyped.found.join("|")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn type_name(value: &Value) -> String {
+    match value {
+        Value::Null => "null".into(),
+        Value::Bool(_) => "bool".into(),
+        Value::Number(n) if n.is_f64() => "float".into(),
+        Value::Number(_) => "int".into(),
+        Value::String(_) => "string".into(),
+        Value::Object(_) => "object".into(),
+        Value::Array(items) => match items.first() {
+            Some(first) => format!("list({})", type_name(first)),
+            None => "list(empty)".into(),
+        },
+    }
+}
+
+/// `url_struct[].short_url` to `url_struct[]`, top level fields to `""`.
+fn prefix_of(path: &str) -> &str {
+    path.rsplit_once('.').map_or("", |(prefix, _)| prefix)
+}
+
+#[cfg(test)]
+mod real_tests {
+    use super::*;
+    use crate::{ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_favorites_match_baseline() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let favorites = weibo_api.favorites_with_raw(1, 20).await.unwrap();
+            let mut schema = Schema::new();
+            schema.observe_statuses(&favorites.raw);
+            let drift = schema.diff(&Schema::status_baseline().unwrap());
+            if !drift.is_empty() {
+                log::warn!("status payload changed:\n{drift}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture(name: &str) -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn fixture_schema() -> Schema {
+        let mut schema = Schema::new();
+        for name in [
+            "favorites.json",
+            "profile_statuses.json",
+            "statuses_show.json",
+            "statuses_show_video.json",
+        ] {
+            schema.observe_statuses(&fixture(name));
+        }
+        schema
+    }
+
+    #[test]
+    fn test_fixtures_match_baseline() {
+        let baseline = Schema::status_baseline().unwrap();
+        let drift = fixture_schema().diff(&baseline);
+        assert!(drift.is_empty(), "status schema drifted:\n{drift}");
+
+        // A status retyped since the baseline was recorded is reported.
+        let mut schema = Schema::new();
+        schema.observe_statuses(&json!({ "mid": 5186000000000001_i64 }));
+        let drift = schema.diff(&baseline);
+        assert_eq!(drift.retyped.len(), 1, "{drift}");
+        assert_eq!(drift.retyped[0].path, "mid");
+    }
+
+    #[test]
+    fn test_schema_save_and_load() {
+        let schema = fixture_schema();
+        assert!(schema.objects[""] > 0);
+        assert!(schema.fields.contains_key("user.screen_name"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status_schema.json");
+        schema.save(&path).unwrap();
+        let baseline = Schema::load(&path).unwrap();
+        assert_eq!(baseline, schema);
+        assert!(schema.diff(&baseline).is_empty());
+    }
+
+    #[test]
+    fn test_schema_observe() {
+        let mut schema = Schema::new();
+        schema.observe(&json!({
+            "id": 1,
+            "retweeted_status": { "id": 2, "text": "retweet" },
+            "pic_infos": {
+                "006pic1": { "large": { "url": "a" } },
+                "006pic2": { "large": { "url": "b" }, "video": null }
+            },
+            "url_struct": [{ "short_url": "c" }]
+        }));
+        assert_eq!(schema.objects[""], 2);
+        assert_eq!(schema.fields["id"].count, 2);
+        assert_eq!(schema.frequency("text"), Some(0.5));
+        assert_eq!(schema.fields["pic_infos.*.large.url"].count, 2);
+        assert_eq!(schema.frequency("pic_infos.*.video"), Some(0.5));
+        assert_eq!(schema.fields["url_struct"].types["list(object)"], 1);
+        assert!(schema.fields.contains_key("url_struct[].short_url"));
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let mut baseline = Schema::new();
+        baseline.observe(&json!({ "id": 1, "text": "a", "user": { "id": 1 }, "source": "" }));
+        baseline.observe(&json!({ "id": 2, "text": "b", "user": { "id": 2 } }));
+
+        let mut schema = Schema::new();
+        schema.observe(&json!({ "id": "3", "user": { "id": 3, "verified": true }, "tags": [] }));
+        let diff = schema.diff(&baseline);
+        assert_eq!(diff.new, ["tags", "user.verified"]);
+        assert_eq!(diff.missing, ["text"]);
+        assert_eq!(
+            diff.retyped,
+            [Retyped {
+                path: "id".into(),
+                expected: vec!["int".into()],
+                found: vec!["string".into()],
+            }]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "+ tags\n+ user.verified\n- text\n~ id: int -> string\n"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Status and user search, e.g. for tracking mentions of a keyword.
+use chrono::{DateTime, FixedOffset};
+use futures::{Stream, StreamExt, TryStreamExt};
+use log::info;
+
+use crate::{
+    api_client::ApiClient,
+    constants::{
+        params::*,
+        urls::{URL_SEARCH_STATUSES, URL_SEARCH_USERS},
+    },
+    error::{ErrorContext, Result, ResultExt},
+    http_client::HttpClient,
+    models::{StatusSearch, UserSearch, WithRaw},
+    paging::{paged_stream, paged_stream_until},
+};
+
+/// Results per page, the most the api returns.
+const PAGE_SIZE: u32 = 20;
+/// The api stops returning results after this page.
+const MAX_PAGES: usize = 50;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchSort {
+    /// Newest first.
+    #[default]
+    Time,
+    /// Most popular first.
+    Hot,
+}
+
+impl SearchSort {
+    fn as_param(self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Hot => "hot",
+        }
+    }
+}
+
+/// What `search_statuses` looks for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusQuery {
+    pub keyword: String,
+    pub sort: SearchSort,
+    /// Only statuses posted at or after this time.
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Only statuses posted before this time.
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl StatusQuery {
+    pub fn new(keyword: &str) -> Self {
+        Self {
+            keyword: keyword.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn sort(mut self, sort: SearchSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<FixedOffset>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<FixedOffset>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Statuses without a time are kept, the api already filtered them.
+    fn contains(&self, created_at: Option<DateTime<FixedOffset>>) -> bool {
+        created_at.is_none_or(|created_at| {
+            self.since.is_none_or(|since| created_at >= since)
+                && self.until.is_none_or(|until| created_at < until)
+        })
+    }
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Statuses matching `query`, pages start at 1. Statuses outside the
+    /// time range of the query are dropped, should the api return any.
+    pub async fn search_statuses(&self, query: &StatusQuery, page: u32) -> Result<StatusSearch> {
+        let mut search = self.search_statuses_with_raw(query, page).await?.value;
+        search
+            .statuses
+            .retain(|status| query.contains(status.created_at));
+        Ok(search)
+    }
+
+    /// Same as `search_statuses` without the time filter, along with the
+    /// JSON as it was received.
+    pub async fn search_statuses_with_raw(
+        &self,
+        query: &StatusQuery,
+        page: u32,
+    ) -> Result<WithRaw<StatusSearch>> {
+        info!("searching statuses, q: {}, page: {page}", query.keyword);
+        let mut params = self.signed_params()?;
+        params["q"] = query.keyword.clone().into();
+        params["sort"] = query.sort.as_param().into();
+        params["page"] = page.into();
+        params["count"] = PAGE_SIZE.into();
+        params["mix_media_enable"] = MIX_MEDIA_ENABLE.into();
+        if let Some(since) = query.since {
+            params["starttime"] = since.timestamp().into();
+        }
+        if let Some(until) = query.until {
+            params["endtime"] = until.timestamp().into();
+        }
+
+        self.get_with_raw(URL_SEARCH_STATUSES, &params)
+            .await
+            .context(
+                ErrorContext::new("search_statuses")
+                    .param("q", &query.keyword)
+                    .param("page", page),
+            )
+    }
+
+    /// Pages of `search_statuses` until the results run out. With
+    /// `SearchSort::Time`, stops at the first status older than `query.since`.
+    pub fn search_statuses_stream<'a>(
+        &'a self,
+        query: &'a StatusQuery,
+    ) -> impl Stream<Item = Result<StatusSearch>> + 'a {
+        let fetch =
+            move |page| async move { Ok(self.search_statuses_with_raw(query, page).await?.value) };
+        let passed_since = move |search: &StatusSearch| {
+            query.sort == SearchSort::Time
+                && query.since.is_some_and(|since| {
+                    search
+                        .statuses
+                        .iter()
+                        .any(|status| status.created_at.is_some_and(|t| t < since))
+                })
+        };
+        paged_stream_until(fetch, passed_since)
+            .take(MAX_PAGES)
+            .map_ok(move |mut search| {
+                search
+                    .statuses
+                    .retain(|status| query.contains(status.created_at));
+                search
+            })
+    }
+
+    /// Users whose name matches `keyword`, pages start at 1.
+    pub async fn search_users(&self, keyword: &str, page: u32) -> Result<UserSearch> {
+        Ok(self.search_users_with_raw(keyword, page).await?.value)
+    }
+
+    /// Same as `search_users`, along with the JSON as it was received.
+    pub async fn search_users_with_raw(
+        &self,
+        keyword: &str,
+        page: u32,
+    ) -> Result<WithRaw<UserSearch>> {
+        info!("searching users, q: {keyword}, page: {page}");
+        let mut params = self.signed_params()?;
+        params["q"] = keyword.into();
+        params["page"] = page.into();
+        params["count"] = PAGE_SIZE.into();
+
+        self.get_with_raw(URL_SEARCH_USERS, &params).await.context(
+            ErrorContext::new("search_users")
+                .param("q", keyword)
+                .param("page", page),
+        )
+    }
+
+    /// Pages of `search_users` until the results run out.
+    pub fn search_users_stream<'a>(
+        &'a self,
+        keyword: &'a str,
+    ) -> impl Stream<Item = Result<UserSearch>> + 'a {
+        paged_stream(move |page| self.search_users(keyword, page)).take(MAX_PAGES)
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use super::*;
+    use crate::{http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_search_statuses() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let _ = weibo_api
+                .search_statuses(&StatusQuery::new("读书"), 1)
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use futures::{StreamExt, TryStreamExt};
+
+    use super::*;
+    use crate::{
+        mock::{MockClient, logged_in_client},
+        models::created_at,
+    };
+
+    fn api_client() -> ApiClient<MockClient> {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_search_statuses_response_from_file(Path::new("tests/data/search_statuses.json"))
+            .unwrap();
+        mock_client
+            .set_search_users_response_from_file(Path::new("tests/data/search_users.json"))
+            .unwrap();
+        logged_in_client(mock_client)
+    }
+
+    #[tokio::test]
+    async fn test_search_statuses() {
+        let weibo_api = api_client();
+        let query = StatusQuery::new("示例品牌");
+        let search = weibo_api.search_statuses(&query, 1).await.unwrap();
+        assert_eq!(search.statuses.len(), 3);
+        assert_eq!(search.total_number, 120);
+
+        let since = created_at::parse("2025-07-08 00:00:00").unwrap();
+        let query = query.since(since);
+        let search = weibo_api.search_statuses(&query, 1).await.unwrap();
+        let ids: Vec<_> = search.statuses.iter().map(|s| s.id).collect();
+        assert_eq!(ids, [5186000000000012, 5186000000000011]);
+    }
+
+    #[tokio::test]
+    async fn test_search_statuses_stream() {
+        let weibo_api = api_client();
+        // The fixture reaches back before `since`, so the first page is the last.
+        let query =
+            StatusQuery::new("示例品牌").since(created_at::parse("2025-07-08 00:00:00").unwrap());
+        let pages: Vec<_> = weibo_api
+            .search_statuses_stream(&query)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].statuses.len(), 2);
+
+        // Without a range it pages on until the api stops.
+        let query = StatusQuery::new("示例品牌").sort(SearchSort::Hot);
+        let pages = weibo_api.search_statuses_stream(&query).count().await;
+        assert_eq!(pages, 40);
+    }
+
+    #[tokio::test]
+    async fn test_search_users() {
+        let weibo_api = api_client();
+        let search = weibo_api.search_users("示例", 1).await.unwrap();
+        assert_eq!(search.users.len(), 2);
+        assert_eq!(search.users[0].screen_name, "示例品牌官方");
+        let pages = weibo_api.search_users_stream("示例").count().await;
+        assert_eq!(pages, 1);
+
+        // Without a total, stops at the last page search returns.
+        weibo_api.client.set_search_users_response_from_str(
+            r#"{"users": [{"id": 1, "screen_name": "示例"}], "total_number": 0}"#,
+        );
+        let pages = weibo_api.search_users_stream("示例").count().await;
+        assert_eq!(pages, MAX_PAGES);
+    }
+}
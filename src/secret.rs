@@ -0,0 +1,182 @@
+//! Keeps credentials out of `Debug` output and trace logs.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const REDACTED: &str = "***";
+
+/// Request parameters and form fields that must never be logged verbatim.
+const SECRET_PARAMS: &[&str] = &[
+    "gsid",
+    "s",
+    "smscode",
+    "alt",
+    "ticket",
+    "cookie",
+    "access_token",
+    "oauth_token",
+    "oauth_token_secret",
+    "sut",
+];
+const PHONE_PARAMS: &[&str] = &["phone", "phone_number"];
+
+/// A value which is serialized as is but prints as `***` in `Debug` output.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+/// Keeps the first three and last two digits, which is enough to tell
+/// accounts apart in a bug report.
+pub(crate) fn mask_phone(phone: &str) -> String {
+    let phone = phone.trim();
+    let chars: Vec<_> = phone.chars().collect();
+    if chars.len() <= 5 {
+        return REDACTED.to_string();
+    }
+    let head: String = chars[..3].iter().collect();
+    let tail: String = chars[chars.len() - 2..].iter().collect();
+    format!("{head}{}{tail}", "*".repeat(chars.len() - 5))
+}
+
+/// Serializes request parameters for logging, with secrets replaced.
+pub(crate) fn redact_params(params: &impl Serialize) -> String {
+    let mut params = serde_json::to_value(params).unwrap_or_default();
+    redact_value(&mut params);
+    serde_json::to_string_pretty(&params).unwrap_or_default()
+}
+
+/// Masks secret query parameters of an url, e.g. sso tickets.
+pub(crate) fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = redact_param(&key, &value).unwrap_or_else(|| value.into_owned());
+            (key.into_owned(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    parsed.to_string()
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let redacted = match value {
+                    Value::String(s) => redact_param(key, s),
+                    Value::Number(n) => redact_param(key, &n.to_string()),
+                    _ => None,
+                };
+                match redacted {
+                    Some(redacted) => *value = Value::String(redacted),
+                    None => redact_value(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn redact_param(key: &str, value: &str) -> Option<String> {
+    if SECRET_PARAMS.iter().any(|k| key.eq_ignore_ascii_case(k)) {
+        Some(REDACTED.to_string())
+    } else if PHONE_PARAMS.iter().any(|k| key.eq_ignore_ascii_case(k)) {
+        Some(mask_phone(value))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_debug() {
+        let secret: Secret<String> = "_2A25gsid".into();
+        assert_eq!(format!("{secret:?}"), "Secret(***)");
+        assert_eq!(secret.expose(), "_2A25gsid");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"_2A25gsid\"");
+    }
+
+    #[test]
+    fn test_mask_phone() {
+        assert_eq!(mask_phone("13800138000"), "138******00");
+        assert_eq!(mask_phone("123"), "***");
+    }
+
+    #[test]
+    fn test_redact_params() {
+        let params = json!({
+            "gsid": "_2A25gsid",
+            "s": "fb111111",
+            "smscode": 123456,
+            "phone": "13800138000",
+            "page": 1,
+            "nested": { "access_token": "token_value" },
+        });
+        let logged = redact_params(&params);
+        for secret in [
+            "_2A25gsid",
+            "fb111111",
+            "123456",
+            "13800138000",
+            "token_value",
+        ] {
+            assert!(!logged.contains(secret), "{secret} leaked in {logged}");
+        }
+        assert!(logged.contains("138******00"));
+        assert!(logged.contains("\"page\": 1"));
+    }
+
+    #[test]
+    fn test_redact_url() {
+        let url = "https://passport.weibo.com/wbsso/login?ticket=ST-123&ssosavestate=1";
+        assert_eq!(
+            redact_url(url),
+            "https://passport.weibo.com/wbsso/login?ticket=***&ssosavestate=1"
+        );
+        assert_eq!(redact_url("https://weibo.com/"), "https://weibo.com/");
+    }
+}
@@ -1,21 +1,45 @@
-use std::{fs, path::Path};
+use std::{fmt, fs, path::Path};
 
 use log::{debug, info};
 use reqwest_cookie_store::CookieStore;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::error::Result;
+use crate::{constants::params::WEB_DOMAIN, cookie, error::Result, secret::Secret};
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Session {
-    pub gsid: String,
+    pub gsid: Secret<String>,
     pub uid: String,
     pub user: Value,
     pub cookie_store: CookieStore,
 }
 
 impl Session {
+    /// A web only session from the cookies of a logged in weibo.com browser,
+    /// e.g. `SUB` and `SUBP`. Uid and user are filled in by
+    /// `ApiClient::login_with_web_session`.
+    pub fn from_web_cookies<K, V>(cookies: impl IntoIterator<Item = (K, V)>) -> Result<Self>
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Ok(Self {
+            cookie_store: cookie::web_cookie_store(WEB_DOMAIN, cookies)?,
+            ..Default::default()
+        })
+    }
+
+    /// Same as `from_web_cookies`, from a raw `Cookie:` header copied from a browser.
+    pub fn from_cookie_header(header: &str) -> Result<Self> {
+        Self::from_web_cookies(cookie::parse_cookie_header(header))
+    }
+
+    /// Sessions built from web cookies have no gsid and can't use the mobile api.
+    pub fn has_gsid(&self) -> bool {
+        !self.gsid.expose().is_empty()
+    }
+
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         info!("Loading session from {:?}", path.as_ref());
         let content = fs::read_to_string(path)?;
@@ -37,4 +61,73 @@ impl Session {
         );
         Ok(())
     }
+
+    /// Writes the cookies in Netscape `cookies.txt` format, for tools like curl or yt-dlp.
+    pub fn export_cookies_txt<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        info!("Exporting cookies to {:?}", path.as_ref());
+        fs::write(path, cookie::to_cookies_txt(&self.cookie_store))?;
+        Ok(())
+    }
+
+    /// Merges the cookies of a Netscape `cookies.txt` file, e.g. a browser export.
+    pub fn import_cookies_txt<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        info!("Importing cookies from {:?}", path.as_ref());
+        let content = fs::read_to_string(path)?;
+        cookie::read_cookies_txt(&mut self.cookie_store, &content)?;
+        debug!(
+            "{} cookies in session after import",
+            self.cookie_store.iter_unexpired().count()
+        );
+        Ok(())
+    }
+}
+
+/// Cookie values are credentials as well, only their names are printed.
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cookie_names: Vec<_> = self
+            .cookie_store
+            .iter_unexpired()
+            .map(|cookie| cookie.name())
+            .collect();
+        cookie_names.sort_unstable();
+        f.debug_struct("Session")
+            .field("gsid", &self.gsid)
+            .field("uid", &self.uid)
+            .field("user", &self.user["screen_name"])
+            .field("cookie_store", &cookie_names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+
+    #[test]
+    fn test_cookies_txt_export_import() {
+        let cookies_txt = "#HttpOnly_.weibo.com\tTRUE\t/\tTRUE\t4102444800\tSUB\tsub\n";
+        let dir = tempfile::tempdir().unwrap();
+        let import_path = dir.path().join("import.txt");
+        fs::write(&import_path, cookies_txt).unwrap();
+
+        let mut session = Session::default();
+        session.import_cookies_txt(&import_path).unwrap();
+        assert!(session.cookie_store.contains("weibo.com", "/", "SUB"));
+
+        let export_path = dir.path().join("export.txt");
+        session.export_cookies_txt(&export_path).unwrap();
+        let exported = fs::read_to_string(export_path).unwrap();
+        assert!(exported.ends_with(cookies_txt));
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let mut session = Session::from_cookie_header("SUB=sub_value").unwrap();
+        session.gsid = "_2A25gsid".into();
+        let debug = format!("{session:?}");
+        assert!(!debug.contains("_2A25gsid"));
+        assert!(!debug.contains("sub_value"));
+        assert!(debug.contains("SUB"));
+    }
 }
@@ -1,21 +1,20 @@
-use log::info;
+use std::collections::{BTreeSet, HashMap};
+
+use futures::{StreamExt, stream};
+use log::{debug, info, warn};
 
 use crate::{
     api_client::ApiClient,
-    constants::{params::*, urls::URL_STATUSES_SHOW},
-    error::Result,
+    constants::urls::URL_STATUSES_SHOW,
+    error::{ErrorContext, Result, ResultExt},
     http_client::HttpClient,
-    utils,
+    models::{LongText, Status, WithRaw},
 };
 
 impl<C: HttpClient> ApiClient<C> {
     pub async fn statuses_show(&self, id: i64) -> Result<C::Response> {
         info!("getting long text, id: {id}");
-        let session = self.session()?;
-        let s = utils::generate_s(&session.uid, FROM);
-        let mut params = utils::build_common_params();
-        params["gsid"] = session.gsid.clone().into();
-        params["s"] = s.into();
+        let mut params = self.signed_params()?;
         params["id"] = id.into();
         params["isGetLongText"] = 1.into();
 
@@ -27,6 +26,80 @@ impl<C: HttpClient> ApiClient<C> {
                 self.config.timeout,
             )
             .await
+            .context(ErrorContext::new("statuses_show").param("id", id))
+    }
+
+    /// Same as `statuses_show`, parsed into the typed model with its long text.
+    pub async fn statuses_show_typed(&self, id: i64) -> Result<Status> {
+        Ok(self.statuses_show_with_raw(id).await?.value)
+    }
+
+    /// Same as `statuses_show_typed`, along with the JSON as it was received.
+    pub async fn statuses_show_with_raw(&self, id: i64) -> Result<WithRaw<Status>> {
+        WithRaw::from_response(self.statuses_show(id).await?)
+            .await
+            .context(ErrorContext::new("statuses_show").param("id", id))
+    }
+
+    /// Fetches the long texts of truncated statuses and of their retweets, at
+    /// most `config.long_text_concurrency` at a time, and replaces their text.
+    /// Statuses whose long text can't be fetched keep the truncated text.
+    /// Returns how many statuses were expanded.
+    pub async fn expand_long_text<'a>(
+        &self,
+        statuses: impl IntoIterator<Item = &'a mut Status>,
+    ) -> usize {
+        let mut statuses: Vec<_> = statuses.into_iter().collect();
+        let ids: BTreeSet<i64> = statuses
+            .iter()
+            .flat_map(|status| [Some(&**status), status.retweeted_status.as_deref()])
+            .flatten()
+            .filter(|status| status.needs_long_text())
+            .map(|status| status.id)
+            .collect();
+        if ids.is_empty() {
+            return 0;
+        }
+        info!("expanding {} long texts", ids.len());
+
+        let long_texts: HashMap<i64, LongText> = stream::iter(ids)
+            .map(|id| async move { (id, self.statuses_show_typed(id).await) })
+            .buffer_unordered(self.config.long_text_concurrency.max(1))
+            .filter_map(|(id, result)| async move {
+                match result {
+                    Ok(status) => status
+                        .long_text
+                        .filter(|long_text| !long_text.content.is_empty())
+                        .map(|long_text| (id, long_text)),
+                    Err(e) => {
+                        warn!("failed to get long text of {id}, keeping truncated text: {e}");
+                        None
+                    }
+                }
+            })
+            .collect()
+            .await;
+
+        let mut expanded = 0;
+        for status in statuses.iter_mut() {
+            expanded += replace_long_text(status, &long_texts);
+            if let Some(retweet) = status.retweeted_status.as_deref_mut() {
+                expanded += replace_long_text(retweet, &long_texts);
+            }
+        }
+        debug!("{expanded} long texts expanded");
+        expanded
+    }
+}
+
+fn replace_long_text(status: &mut Status, long_texts: &HashMap<i64, LongText>) -> usize {
+    match long_texts.get(&status.id) {
+        Some(long_text) if status.needs_long_text() => {
+            status.text = long_text.content.clone();
+            status.long_text = Some(long_text.clone());
+            1
+        }
+        _ => 0,
     }
 }
 
@@ -44,3 +117,32 @@ mod real_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use crate::{
+        mock::{MockClient, logged_in_client},
+        models::Status,
+    };
+
+    #[tokio::test]
+    async fn test_expand_long_text() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_statuses_show_response_from_file(Path::new("tests/data/statuses_show.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+        let mut status = Status {
+            id: 5186001122334455,
+            text: "读书笔记 ...".to_string(),
+            is_long_text: true,
+            ..Default::default()
+        };
+        assert_eq!(weibo_api.expand_long_text([&mut status]).await, 1);
+        assert!(!status.needs_long_text());
+        assert!(status.text.ends_with("第二段 & 结尾"));
+        assert!(status.full_text().ends_with("第二段 & 结尾"));
+    }
+}
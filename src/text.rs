@@ -0,0 +1,535 @@
+//! Tokenizer for status text.
+//!
+//! Handles both the plain text of the mobile api (and the `text_raw` field of
+//! the web api), where mentions, topics, links and emoticons are inline
+//! markup, and the HTML `text` field of the web api, where they are anchors
+//! and images.
+use serde::{Deserialize, Serialize};
+
+use crate::emoji::EmojiCatalog;
+
+/// The longest emoticon phrase, brackets excluded, that is looked for.
+const MAX_EMOTICON_LEN: usize = 12;
+const MAX_HASHTAG_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TextSegment {
+    Plain(String),
+    LineBreak,
+    /// Screen name of the mentioned user, without `@`.
+    Mention(String),
+    /// Topic name, without the surrounding `#`.
+    Hashtag(String),
+    Link(Link),
+    Emoticon(Emoticon),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub url: String,
+    pub title: Option<String>,
+    pub url_struct: Option<UrlStruct>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Emoticon {
+    /// Brackets included, e.g. `[doge]`.
+    pub phrase: String,
+    pub url: Option<String>,
+}
+
+/// An entry of the `url_struct` array of a status, describing a short link of its text.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UrlStruct {
+    #[serde(default)]
+    pub short_url: String,
+    pub ori_url: Option<String>,
+    pub long_url: Option<String>,
+    pub url_title: Option<String>,
+    pub url_type_pic: Option<String>,
+    pub page_id: Option<String>,
+}
+
+impl Link {
+    /// The target behind short links and `weibo.cn/sinaurl` redirects.
+    pub fn resolved_url(&self) -> String {
+        let long_url = self.url_struct.as_ref().and_then(|url_struct| {
+            [&url_struct.long_url, &url_struct.ori_url]
+                .into_iter()
+                .flatten()
+                .find(|url| url.starts_with("http"))
+        });
+        if let Some(long_url) = long_url {
+            return long_url.clone();
+        }
+        url::Url::parse(&self.url)
+            .ok()
+            .filter(|url| url.path().ends_with("/sinaurl"))
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == "u")
+                    .map(|(_, target)| target.into_owned())
+            })
+            .unwrap_or_else(|| self.url.clone())
+    }
+}
+
+impl UrlStruct {
+    fn matches(&self, url: &str) -> bool {
+        !url.is_empty()
+            && (self.short_url == url
+                || self.ori_url.as_deref() == Some(url)
+                || self.long_url.as_deref() == Some(url))
+    }
+}
+
+/// Splits status text into segments. Links are completed with the matching
+/// `url_structs` entry. With an emoji catalog only known phrases become
+/// emoticons; without one every short bracketed phrase does.
+pub fn tokenize(
+    text: &str,
+    url_structs: &[UrlStruct],
+    emojis: Option<&EmojiCatalog>,
+) -> Vec<TextSegment> {
+    let tokenizer = Tokenizer {
+        url_structs,
+        emojis,
+        segments: Vec::new(),
+    };
+    if looks_like_html(text) {
+        tokenizer.tokenize_html(text)
+    } else {
+        tokenizer.tokenize_plain(text)
+    }
+}
+
+/// The text with markup removed, emoticons kept as their phrase.
+pub fn to_plain_text(segments: &[TextSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            TextSegment::Plain(text) => text.clone(),
+            TextSegment::LineBreak => "\n".to_string(),
+            TextSegment::Mention(name) => format!("@{name}"),
+            TextSegment::Hashtag(topic) => format!("#{topic}#"),
+            TextSegment::Link(link) => link.title.clone().unwrap_or_else(|| link.url.clone()),
+            TextSegment::Emoticon(emoticon) => emoticon.phrase.clone(),
+        })
+        .collect()
+}
+
+struct Tokenizer<'a> {
+    url_structs: &'a [UrlStruct],
+    emojis: Option<&'a EmojiCatalog>,
+    segments: Vec<TextSegment>,
+}
+
+impl Tokenizer<'_> {
+    fn tokenize_plain(mut self, text: &str) -> Vec<TextSegment> {
+        self.push_plain_markup(text);
+        self.segments
+    }
+
+    fn tokenize_html(mut self, html: &str) -> Vec<TextSegment> {
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            self.push_plain_markup(&decode_entities(&rest[..start]));
+            let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+                rest = &rest[start..];
+                break;
+            };
+            let tag = Tag::parse(&rest[start + 1..end]);
+            rest = &rest[end + 1..];
+            match tag.name.as_str() {
+                "br" => self.segments.push(TextSegment::LineBreak),
+                "img" => {
+                    if let Some(alt) = tag.attr("alt").filter(|alt| is_bracketed(alt)) {
+                        let src = tag.attr("src");
+                        self.push_emoticon(alt, src);
+                    }
+                }
+                "a" if !tag.closing => {
+                    let (inner_html, after) = match rest.find("</a>") {
+                        Some(close) => (&rest[..close], &rest[close + 4..]),
+                        None => (rest, ""),
+                    };
+                    rest = after;
+                    self.push_anchor(&tag, inner_html);
+                }
+                _ => {}
+            }
+        }
+        self.push_plain_markup(&decode_entities(rest));
+        self.segments
+    }
+
+    fn push_anchor(&mut self, tag: &Tag, inner_html: &str) {
+        let inner = decode_entities(&strip_tags(inner_html));
+        let inner = inner.trim();
+        let href = tag.attr("href").map(decode_entities).unwrap_or_default();
+        if let Some(name) = inner.strip_prefix('@').filter(|name| !name.is_empty()) {
+            self.segments.push(TextSegment::Mention(name.to_string()));
+        } else if let Some(topic) = inner
+            .strip_prefix('#')
+            .and_then(|topic| topic.strip_suffix('#'))
+            .filter(|topic| !topic.is_empty())
+        {
+            self.segments.push(TextSegment::Hashtag(topic.to_string()));
+        } else if href.is_empty() {
+            self.push_plain(inner);
+        } else {
+            let title = (!inner.is_empty()).then(|| inner.to_string());
+            self.push_link(&href, title);
+        }
+    }
+
+    fn push_plain_markup(&mut self, text: &str) {
+        let mut plain_start = 0;
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let c = rest.chars().next().unwrap();
+            let token = match c {
+                '\n' => Some((TextSegment::LineBreak, 1)),
+                '@' => scan_mention(rest).map(|(name, len)| (TextSegment::Mention(name), len)),
+                '#' => scan_hashtag(rest).map(|(topic, len)| (TextSegment::Hashtag(topic), len)),
+                '[' => self.scan_emoticon(rest),
+                'h' if rest.starts_with("http://") || rest.starts_with("https://") => {
+                    let len = rest.find(is_url_end).unwrap_or(rest.len());
+                    Some((self.link(&rest[..len], None), len))
+                }
+                _ => None,
+            };
+            match token {
+                Some((segment, len)) => {
+                    self.push_plain(&text[plain_start..i]);
+                    self.segments.push(segment);
+                    i += len;
+                    plain_start = i;
+                }
+                None => i += c.len_utf8(),
+            }
+        }
+        self.push_plain(&text[plain_start..]);
+    }
+
+    fn scan_emoticon(&self, text: &str) -> Option<(TextSegment, usize)> {
+        let end = text.find(']')?;
+        let phrase = &text[..=end];
+        if phrase[1..end].is_empty()
+            || phrase[1..end].chars().count() > MAX_EMOTICON_LEN
+            || phrase[1..end].contains(['[', '\n'])
+        {
+            return None;
+        }
+        let url = match self.emojis {
+            Some(emojis) => Some(emojis.url(phrase)?.to_string()),
+            None => None,
+        };
+        let emoticon = Emoticon {
+            phrase: phrase.to_string(),
+            url,
+        };
+        Some((TextSegment::Emoticon(emoticon), phrase.len()))
+    }
+
+    fn push_emoticon(&mut self, phrase: &str, src: Option<&str>) {
+        let url = self
+            .emojis
+            .and_then(|emojis| emojis.url(phrase))
+            .or(src)
+            .map(str::to_string);
+        self.segments.push(TextSegment::Emoticon(Emoticon {
+            phrase: phrase.to_string(),
+            url,
+        }));
+    }
+
+    fn push_link(&mut self, url: &str, title: Option<String>) {
+        let link = self.link(url, title);
+        self.segments.push(link);
+    }
+
+    fn link(&self, url: &str, title: Option<String>) -> TextSegment {
+        let url_struct = self
+            .url_structs
+            .iter()
+            .find(|url_struct| url_struct.matches(url))
+            .cloned();
+        let title = title.or_else(|| url_struct.as_ref().and_then(|u| u.url_title.clone()));
+        TextSegment::Link(Link {
+            url: url.to_string(),
+            title,
+            url_struct,
+        })
+    }
+
+    fn push_plain(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(TextSegment::Plain(last)) = self.segments.last_mut() {
+            last.push_str(text);
+        } else {
+            self.segments.push(TextSegment::Plain(text.to_string()));
+        }
+    }
+}
+
+fn scan_mention(text: &str) -> Option<(String, usize)> {
+    let name_len = text[1..]
+        .find(|c: char| !is_name_char(c))
+        .unwrap_or(text.len() - 1);
+    (name_len > 0).then(|| (text[1..1 + name_len].to_string(), 1 + name_len))
+}
+
+fn scan_hashtag(text: &str) -> Option<(String, usize)> {
+    let end = text[1..].find(['#', '\n'])? + 1;
+    let topic = &text[1..end];
+    (text[end..].starts_with('#')
+        && !topic.trim().is_empty()
+        && topic.chars().count() <= MAX_HASHTAG_LEN)
+        .then(|| (topic.to_string(), end + 1))
+}
+
+/// Nicknames are made of letters, digits, CJK characters, `_` and `-`.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn is_url_end(c: char) -> bool {
+    c.is_whitespace() || !c.is_ascii() || matches!(c, '"' | '<' | '>' | '[' | ']')
+}
+
+fn is_bracketed(s: &str) -> bool {
+    s.len() > 2 && s.starts_with('[') && s.ends_with(']')
+}
+
+fn looks_like_html(text: &str) -> bool {
+    ["<a ", "<br", "<img ", "<span"]
+        .iter()
+        .any(|tag| text.contains(tag))
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attrs: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Tag<'a> {
+    fn parse(tag: &'a str) -> Self {
+        let tag = tag.trim().trim_end_matches('/').trim_end();
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut attrs = Vec::new();
+        let mut rest = &tag[name_end..];
+        while let Some(eq) = rest.find('=') {
+            let key = rest[..eq].trim();
+            let value_part = rest[eq + 1..].trim_start();
+            let (value, after) = match value_part.chars().next() {
+                Some(quote @ ('"' | '\'')) => match value_part[1..].find(quote) {
+                    Some(end) => (&value_part[1..end + 1], &value_part[end + 2..]),
+                    None => (&value_part[1..], ""),
+                },
+                _ => {
+                    let end = value_part
+                        .find(char::is_whitespace)
+                        .unwrap_or(value_part.len());
+                    (&value_part[..end], &value_part[end..])
+                }
+            };
+            // Valueless attributes before `key` end up in front of it.
+            let key = key.rsplit(char::is_whitespace).next().unwrap_or(key);
+            attrs.push((key, value));
+            rest = after;
+        }
+        Self {
+            name: tag[..name_end].to_ascii_lowercase(),
+            closing,
+            attrs,
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&'a str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::emoji::EmojiSource;
+    use serde_json::json;
+
+    fn catalog() -> EmojiCatalog {
+        let mut catalog = EmojiCatalog::default();
+        catalog.merge_response(
+            &json!({ "默认": [{ "phrase": "[doge]", "url": "https://face.t.sinajs.cn/doge.png" }] }),
+            EmojiSource::Web,
+        );
+        catalog
+    }
+
+    fn plain(text: &str) -> TextSegment {
+        TextSegment::Plain(text.to_string())
+    }
+
+    #[test]
+    fn test_tokenize_plain() {
+        let url_structs = [UrlStruct {
+            short_url: "http://t.cn/A6abcd".to_string(),
+            url_title: Some("网页链接".to_string()),
+            ..Default::default()
+        }];
+        let catalog = catalog();
+        let segments = tokenize(
+            "转发 @微博用户_1：#话题名#[doge][不存在] http://t.cn/A6abcd 看看\n完",
+            &url_structs,
+            Some(&catalog),
+        );
+        assert_eq!(
+            segments,
+            vec![
+                plain("转发 "),
+                TextSegment::Mention("微博用户_1".to_string()),
+                plain("："),
+                TextSegment::Hashtag("话题名".to_string()),
+                TextSegment::Emoticon(Emoticon {
+                    phrase: "[doge]".to_string(),
+                    url: Some("https://face.t.sinajs.cn/doge.png".to_string()),
+                }),
+                plain("[不存在] "),
+                TextSegment::Link(Link {
+                    url: "http://t.cn/A6abcd".to_string(),
+                    title: Some("网页链接".to_string()),
+                    url_struct: Some(url_structs[0].clone()),
+                }),
+                plain(" 看看"),
+                TextSegment::LineBreak,
+                plain("完"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_without_catalog() {
+        let segments = tokenize("a # b [哈哈]", &[], None);
+        assert_eq!(
+            segments,
+            vec![
+                plain("a # b "),
+                TextSegment::Emoticon(Emoticon {
+                    phrase: "[哈哈]".to_string(),
+                    url: None,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_html() {
+        let html = concat!(
+            r#"<a href=/n/微博用户 usercard="name=微博用户">@微博用户</a>:"#,
+            r#"<a href="https://m.weibo.cn/search?containerid=231522type%3D1" data-hide="">#话题名#</a>"#,
+            r#" 1 &lt; 2 &amp;<br />"#,
+            r#"<span class="url-icon"><img alt=[doge] src="https://h5.sinaimg.cn/doge.png" style="width:1em; height:1em;" /></span>"#,
+            r#"<a target="_blank" href="https://weibo.cn/sinaurl?u=https%3A%2F%2Fexample.com"><img class="icon-link" src="https://h5.sinaimg.cn/link.png"/>网页链接</a>"#,
+        );
+        let segments = tokenize(html, &[], None);
+        assert_eq!(
+            segments,
+            vec![
+                TextSegment::Mention("微博用户".to_string()),
+                plain(":"),
+                TextSegment::Hashtag("话题名".to_string()),
+                plain(" 1 < 2 &"),
+                TextSegment::LineBreak,
+                TextSegment::Emoticon(Emoticon {
+                    phrase: "[doge]".to_string(),
+                    url: Some("https://h5.sinaimg.cn/doge.png".to_string()),
+                }),
+                TextSegment::Link(Link {
+                    url: "https://weibo.cn/sinaurl?u=https%3A%2F%2Fexample.com".to_string(),
+                    title: Some("网页链接".to_string()),
+                    url_struct: None,
+                }),
+            ]
+        );
+        assert_eq!(
+            to_plain_text(&segments),
+            "@微博用户:#话题名# 1 < 2 &\n[doge]网页链接"
+        );
+        let TextSegment::Link(link) = &segments[6] else {
+            panic!("not a link: {:?}", segments[6]);
+        };
+        assert_eq!(link.resolved_url(), "https://example.com");
+    }
+}
@@ -0,0 +1,162 @@
+//! The hot search list and the statuses of `#topic#` pages.
+use log::{error, info};
+use serde_json::{Value, json};
+
+use crate::{
+    api_client::{ApiClient, ErrResponse},
+    constants::urls::{URL_CARDLIST, URL_WEB_HOT_SEARCH},
+    error::{Error, Result, ResultExt},
+    http_client::{HttpClient, HttpResponse},
+    models::{CardList, HotSearchEntry, WithRaw, from_value},
+    profile_statuses::ContainerQuery,
+};
+
+/// Statuses per page of a topic.
+const PAGE_SIZE: u32 = 20;
+
+/// The container of the statuses under a topic, with or without the
+/// surrounding `#`.
+pub fn topic_container_id(topic: &str) -> String {
+    format!("231522type=1&q=#{}#", topic.trim_matches('#'))
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    /// The hot search list by rank, without ads.
+    pub async fn hot_search(&self) -> Result<Vec<HotSearchEntry>> {
+        Ok(self.hot_search_with_raw().await?.value)
+    }
+
+    /// Same as `hot_search`, along with the JSON as it was received.
+    pub async fn hot_search_with_raw(&self) -> Result<WithRaw<Vec<HotSearchEntry>>> {
+        info!("getting hot search");
+        let raw = self
+            .client
+            .get(
+                URL_WEB_HOT_SEARCH,
+                &json!({}),
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context("hot_search")?
+            .json::<Value>()
+            .await
+            .context("hot_search")?;
+        if raw["ok"] != 1 {
+            error!("failed to get hot search: {raw}");
+            return Err(Error::ApiError(ErrResponse {
+                errmsg: raw["msg"].as_str().unwrap_or_default().to_string(),
+                errno: raw["ok"].as_i64().unwrap_or_default() as i32,
+                errtype: "web".into(),
+                isblock: false,
+            }));
+        }
+        let mut entries: Vec<HotSearchEntry> = match &raw["data"]["realtime"] {
+            Value::Null => Vec::new(),
+            realtime => from_value(realtime).context("hot_search")?,
+        };
+        entries.retain(|entry| !entry.is_ad && entry.rank > 0);
+        entries.sort_by_key(|entry| entry.rank);
+        Ok(WithRaw {
+            value: entries,
+            raw,
+        })
+    }
+
+    /// Statuses under `topic`, e.g. `#周末读书#`, pages start at 1. Truncated
+    /// texts are expanded when `config.expand_long_text` is set.
+    pub async fn topic_statuses(&self, topic: &str, page: u32) -> Result<CardList> {
+        Ok(self.topic_statuses_with_raw(topic, page).await?.value)
+    }
+
+    /// Same as `topic_statuses`, along with the JSON as it was received.
+    pub async fn topic_statuses_with_raw(
+        &self,
+        topic: &str,
+        page: u32,
+    ) -> Result<WithRaw<CardList>> {
+        let query = ContainerQuery {
+            endpoint: "topic_statuses",
+            url: URL_CARDLIST,
+            uid: None,
+            container_id: topic_container_id(topic),
+            page,
+        };
+        self.container_statuses_with_raw(&query, PAGE_SIZE).await
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use crate::{api_client::ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_hot_search() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let entries = weibo_api.hot_search().await.unwrap();
+            assert!(entries.windows(2).all(|w| w[0].rank < w[1].rank));
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        mock::{MockClient, logged_in_client},
+        models::HotLabel,
+    };
+
+    #[tokio::test]
+    async fn test_hot_search() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_web_hot_search_response_from_file(Path::new("tests/data/web_hot_search.json"))
+            .unwrap();
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        let entries = weibo_api.hot_search().await.unwrap();
+
+        let words: Vec<_> = entries.iter().map(|e| e.word.as_str()).collect();
+        assert_eq!(words, ["示例热搜一", "示例热搜二", "示例热搜三"]);
+        assert_eq!(entries[0].heat, 1234567);
+        assert_eq!(entries[0].label(), Some(HotLabel::Boom));
+        assert_eq!(entries[1].label(), Some(HotLabel::New));
+        assert_eq!(entries[2].label(), None);
+        assert_eq!(
+            entries[0].categories().collect::<Vec<_>>(),
+            ["社会新闻", "民生新闻"]
+        );
+        assert_eq!(
+            entries[0].url(),
+            "https://s.weibo.com/weibo?q=%23%E7%A4%BA%E4%BE%8B%E7%83%AD%E6%90%9C%E4%B8%80%23"
+        );
+
+        weibo_api
+            .client
+            .set_web_hot_search_response_from_str(r#"{"ok": -100, "msg": "请先登录"}"#);
+        let error = weibo_api.hot_search().await.unwrap_err();
+        assert!(matches!(error.kind(), Error::ApiError(e) if e.errno == -100));
+    }
+
+    #[tokio::test]
+    async fn test_topic_statuses() {
+        assert_eq!(
+            topic_container_id("#周末读书#"),
+            "231522type=1&q=#周末读书#"
+        );
+        assert_eq!(topic_container_id("周末读书"), "231522type=1&q=#周末读书#");
+
+        let mock_client = MockClient::new();
+        mock_client
+            .set_cardlist_response_from_file(Path::new("tests/data/profile_statuses.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+        let cards = weibo_api.topic_statuses("#周末读书#", 1).await.unwrap();
+        assert!(!cards.statuses().is_empty());
+    }
+}
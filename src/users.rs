@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+
+use log::{debug, info};
+use serde_json::Value;
+
+use crate::{
+    api_client::ApiClient,
+    constants::urls::URL_USERS_SHOW,
+    error::{ErrorContext, Result, ResultExt},
+    http_client::HttpClient,
+    models::{User, WithRaw},
+};
+
+/// Screen names to uids, most recently used first.
+#[derive(Debug, Default)]
+pub(crate) struct ScreenNameCache {
+    entries: VecDeque<(String, i64)>,
+}
+
+impl ScreenNameCache {
+    fn get(&mut self, screen_name: &str) -> Option<i64> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(name, _)| name == screen_name)?;
+        let entry = self.entries.remove(index)?;
+        let uid = entry.1;
+        self.entries.push_front(entry);
+        Some(uid)
+    }
+
+    fn insert(&mut self, screen_name: &str, uid: i64, capacity: usize) {
+        self.entries.retain(|(name, _)| name != screen_name);
+        self.entries.push_front((screen_name.to_string(), uid));
+        self.entries.truncate(capacity);
+    }
+}
+
+impl<C: HttpClient> ApiClient<C> {
+    pub async fn user_show(&self, uid: i64) -> Result<User> {
+        Ok(self.user_show_with_raw(uid).await?.value)
+    }
+
+    /// Same as `user_show`, along with the JSON as it was received.
+    pub async fn user_show_with_raw(&self, uid: i64) -> Result<WithRaw<User>> {
+        info!("getting user, uid: {uid}");
+        self.users_show("uid", uid.into())
+            .await
+            .context(ErrorContext::new("user_show").param("uid", uid))
+    }
+
+    /// Looks up a user by screen name, with or without the `@` of mentions.
+    pub async fn user_by_screen_name(&self, screen_name: &str) -> Result<User> {
+        Ok(self.user_by_screen_name_with_raw(screen_name).await?.value)
+    }
+
+    /// Same as `user_by_screen_name`, along with the JSON as it was received.
+    pub async fn user_by_screen_name_with_raw(&self, screen_name: &str) -> Result<WithRaw<User>> {
+        let screen_name = screen_name.trim_start_matches('@');
+        info!("getting user, screen name: {screen_name}");
+        self.users_show("screen_name", screen_name.into())
+            .await
+            .context(ErrorContext::new("user_by_screen_name").param("screen_name", screen_name))
+    }
+
+    /// The uid of a screen name, from the cache of the last
+    /// `config.screen_name_cache_size` names looked up.
+    pub async fn resolve_uid(&self, screen_name: &str) -> Result<i64> {
+        let screen_name = screen_name.trim_start_matches('@');
+        let cached = self
+            .screen_names
+            .lock()
+            .expect("screen names lock failed")
+            .get(screen_name);
+        if let Some(uid) = cached {
+            debug!("screen name {screen_name} resolved from cache");
+            return Ok(uid);
+        }
+        Ok(self.user_by_screen_name(screen_name).await?.id)
+    }
+
+    async fn users_show(&self, key: &str, value: Value) -> Result<WithRaw<User>> {
+        let mut params = self.signed_params()?;
+        params[key] = value;
+
+        let user: WithRaw<User> = self.get_with_raw(URL_USERS_SHOW, &params).await?;
+        if !user.value.screen_name.is_empty() {
+            self.screen_names
+                .lock()
+                .expect("screen names lock failed")
+                .insert(
+                    &user.value.screen_name,
+                    user.value.id,
+                    self.config.screen_name_cache_size,
+                );
+        }
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod real_tests {
+    use crate::{api_client::ApiClient, http_client, session::Session};
+
+    #[tokio::test]
+    async fn test_real_user_show() {
+        let session_file = "session.json";
+        if let Ok(session) = Session::load(session_file) {
+            let client = http_client::Client::new().unwrap();
+            let weibo_api = ApiClient::from_session(client, session);
+            let _ = weibo_api.user_show(1669879400).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        error::Error,
+        mock::{MockClient, logged_in_client},
+    };
+
+    fn api_client() -> ApiClient<MockClient> {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_users_show_response_from_file(Path::new("tests/data/users_show.json"))
+            .unwrap();
+        logged_in_client(mock_client)
+    }
+
+    #[tokio::test]
+    async fn test_user_show() {
+        let weibo_api = api_client();
+        let user = weibo_api.user_show(1234567890).await.unwrap();
+        assert_eq!(user.id, 1234567890);
+        assert_eq!(user.screen_name, "示例作者");
+        assert_eq!(user.followers_count, 12000);
+        assert!(user.verified);
+        assert_eq!(
+            user.created_at.unwrap().to_rfc3339(),
+            "2012-03-04T10:20:30+08:00"
+        );
+
+        let user = weibo_api.user_by_screen_name_with_raw("@示例作者").await;
+        assert_eq!(user.unwrap().raw["location"], "北京 海淀区");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_uid() {
+        let weibo_api = api_client();
+        assert_eq!(
+            weibo_api.resolve_uid("@示例作者").await.unwrap(),
+            1234567890
+        );
+
+        // Cached names don't hit the api anymore.
+        weibo_api.client.set_users_show_response_from_str(
+            r#"{"errmsg": "User does not exists!", "errno": 20003, "errtype": "DEFAULT_ERROR", "isblock": false}"#,
+        );
+        assert_eq!(weibo_api.resolve_uid("示例作者").await.unwrap(), 1234567890);
+        let error = weibo_api.resolve_uid("someone_else").await.unwrap_err();
+        assert!(matches!(error.kind(), Error::ApiError(e) if e.errno == 20003));
+        assert_eq!(
+            error.context().unwrap().params["screen_name"],
+            "someone_else"
+        );
+    }
+
+    #[test]
+    fn test_screen_name_cache() {
+        let mut cache = ScreenNameCache::default();
+        cache.insert("a", 1, 2);
+        cache.insert("b", 2, 2);
+        assert_eq!(cache.get("a"), Some(1));
+        cache.insert("c", 3, 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+    }
+}
@@ -0,0 +1,104 @@
+use log::{debug, info};
+
+use crate::{
+    api_client::ApiClient,
+    error::Result,
+    http_client::HttpClient,
+    models::{Status, VideoInfo},
+};
+
+impl<C: HttpClient> ApiClient<C> {
+    /// The video of a status, or of the status it retweets. Timeline payloads
+    /// often lack playback data, the status is then fetched with `statuses_show`.
+    /// Returns `None` for statuses without video.
+    pub async fn resolve_video(&self, status: &Status) -> Result<Option<VideoInfo>> {
+        let Some(video_status) = [Some(status), status.retweeted_status.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|status| status.page_info.as_ref().is_some_and(|p| p.is_video()))
+        else {
+            return Ok(None);
+        };
+        if let Some(video) = video_status.page_info.as_ref().and_then(|p| p.video_info()) {
+            return Ok(Some(video));
+        }
+
+        info!(
+            "no playback data in payload, getting status {}",
+            video_status.id
+        );
+        let full_status = self.statuses_show_typed(video_status.id).await?;
+        let video = full_status.page_info.and_then(|p| p.video_info());
+        if video.is_none() {
+            debug!("status {} has no playable video", video_status.id);
+        }
+        Ok(video)
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        mock::{MockClient, logged_in_client},
+        models::PageInfo,
+    };
+
+    fn video_status() -> Status {
+        let content = std::fs::read_to_string("tests/data/statuses_show_video.json").unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolve_video_from_payload() {
+        let weibo_api = logged_in_client(MockClient::new());
+        let video = weibo_api
+            .resolve_video(&video_status())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(video.title, "示例视频");
+        assert_eq!(video.duration, 12);
+        assert_eq!(
+            video.cover.as_deref(),
+            Some("https://wx3.sinaimg.cn/orj480/cover.jpg")
+        );
+        let labels: Vec<_> = video.renditions.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["mp4_1080p", "mp4_720p", "mp4_hd", "stream_url"]);
+        let best = &video.renditions[0];
+        assert_eq!(best.video_codec.as_deref(), Some("hev1.1.6.L120.90"));
+        assert_eq!((best.width, best.height), (Some(1920), Some(1080)));
+        assert_eq!(best.bitrate, Some(3000000));
+        assert_eq!(video.renditions[1].size, Some(2400000));
+        assert_eq!(video.renditions[3].height, None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_video_falls_back_to_statuses_show() {
+        let mock_client = MockClient::new();
+        mock_client
+            .set_statuses_show_response_from_file(Path::new("tests/data/statuses_show_video.json"))
+            .unwrap();
+        let weibo_api = logged_in_client(mock_client);
+        let retweet = Status {
+            id: 5187000000000001,
+            page_info: Some(PageInfo {
+                kind: "video".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let status = Status {
+            retweeted_status: Some(Box::new(retweet)),
+            ..Default::default()
+        };
+        let video = weibo_api.resolve_video(&status).await.unwrap().unwrap();
+        assert_eq!(video.object_id, "1034:5187000000000009");
+        assert_eq!(video.renditions.len(), 4);
+
+        let text_only = Status::default();
+        assert_eq!(weibo_api.resolve_video(&text_only).await.unwrap(), None);
+    }
+}
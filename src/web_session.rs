@@ -0,0 +1,142 @@
+use log::{debug, error, info};
+use serde_json::{Value, json};
+
+use crate::{
+    api_client::{ApiClient, LoginState},
+    constants::urls::{URL_WEB_CONFIG, URL_WEB_PROFILE_INFO},
+    error::{Error, ErrorContext, Result, ResultExt},
+    http_client::{HttpClient, HttpResponse},
+    session::Session,
+};
+
+impl<C: HttpClient> ApiClient<C> {
+    /// Logs in with a web only session, e.g. one from `Session::from_cookie_header`.
+    /// The uid and user are looked up with the cookies, the web api endpoints
+    /// are usable afterwards while the mobile api ones fail with `GsidRequired`.
+    pub async fn login_with_web_session(&self, mut session: Session) -> Result<()> {
+        info!("logging in with web cookies");
+        if !self.login_state().is_init() {
+            error!("login_with_web_session called in invalid state");
+            return Err(Error::NotLoggedIn);
+        }
+
+        // The cookies are only kept once they are known to be logged in.
+        let previous = self.client.get_cookie()?;
+        self.client.set_cookie(session.cookie_store.clone())?;
+        let (uid, user) = match self.web_session_user().await {
+            Ok(found) => found,
+            Err(e) => {
+                self.client.clear_cookie()?;
+                self.client.set_cookie(previous)?;
+                return Err(e);
+            }
+        };
+
+        session.user = user;
+        session.uid = uid;
+        session.cookie_store = self.client.get_cookie()?;
+        info!("login with web cookies success, user: {}", session.uid);
+        self.set_login_state(LoginState::LoggedIn { session });
+        Ok(())
+    }
+
+    /// The uid and user the cookies in the client belong to.
+    async fn web_session_user(&self) -> Result<(String, Value)> {
+        let config = self
+            .client
+            .get(
+                URL_WEB_CONFIG,
+                &json!({}),
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context("login_with_web_session")?
+            .json::<Value>()
+            .await?;
+        let uid = match &config["data"]["uid"] {
+            Value::String(uid) if !uid.is_empty() => uid.clone(),
+            Value::Number(uid) => uid.to_string(),
+            _ => {
+                error!("web cookies are not logged in: {config}");
+                return Err(Error::SessionExpired);
+            }
+        };
+        debug!("web cookies belong to user {uid}");
+        let user = self.web_user(&uid).await?;
+        Ok((uid, user))
+    }
+
+    pub(crate) async fn web_user(&self, uid: &str) -> Result<Value> {
+        let mut profile = self
+            .client
+            .get(
+                URL_WEB_PROFILE_INFO,
+                &json!({ "uid": uid }),
+                self.config.retry_times,
+                self.config.timeout,
+            )
+            .await
+            .context(ErrorContext::new("web_user").param("uid", uid))?
+            .json::<Value>()
+            .await?;
+        Ok(profile["data"]["user"].take())
+    }
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::mock::MockClient;
+
+    fn mock_profile_info(client: &MockClient) {
+        client.set_web_profile_info_response_from_str(
+            &json!({
+                "ok": 1,
+                "data": { "user": { "idstr": "1234567890", "screen_name": "example_name" } },
+            })
+            .to_string(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_login_with_web_session() {
+        let mock_client = MockClient::new();
+        mock_client.set_web_config_response_from_str(
+            &json!({ "ok": 1, "data": { "uid": 1234567890 } }).to_string(),
+        );
+        mock_profile_info(&mock_client);
+
+        let session = Session::from_cookie_header("SUB=sub; SUBP=subp").unwrap();
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        weibo_api.login_with_session(session).await.unwrap();
+
+        let session = weibo_api.session().unwrap();
+        assert_eq!(session.uid, "1234567890");
+        assert_eq!(session.user["screen_name"], "example_name");
+        assert!(session.cookie_store.contains("weibo.com", "/", "SUBP"));
+        assert!(matches!(
+            weibo_api.favorites(1, 20).await,
+            Err(Error::GsidRequired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_with_expired_web_cookies() {
+        let mock_client = MockClient::new();
+        mock_client.set_web_config_response_from_str(
+            &json!({ "ok": -100, "url": "https://passport.weibo.com/" }).to_string(),
+        );
+        mock_profile_info(&mock_client);
+
+        let session = Session::from_web_cookies([("SUB", "expired")]).unwrap();
+        let weibo_api = ApiClient::new(mock_client, Default::default());
+        assert!(matches!(
+            weibo_api.login_with_web_session(session).await,
+            Err(Error::SessionExpired)
+        ));
+        assert!(weibo_api.login_state().is_init());
+        let cookies = weibo_api.client.get_cookie().unwrap();
+        assert_eq!(cookies.iter_any().count(), 0);
+    }
+}